@@ -60,7 +60,7 @@ export default function Kitties (props) {
     for (let i = 0; i < kittyDNAs.length; ++i) {
       const kitty = {}
       kitty.id = i
-      kitty.dna = kittyDNAs[i].unwrap()
+      kitty.dna = kittyDNAs[i].unwrap().dna
       kitty.owner = keyring.encodeAddress(kittyOwners[i].unwrap())
       kitties[i] = kitty
     }
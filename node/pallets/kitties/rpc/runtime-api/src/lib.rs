@@ -0,0 +1,55 @@
+//! Runtime API definition for the kitties pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+pub use pallet_kitties::{Kitty, KittyFlags, Provenance};
+
+sp_api::decl_runtime_apis! {
+	/// Read-only queries into the kitties pallet, so clients don't have to scan its storage.
+	pub trait KittiesApi<AccountId, KittyIndex, Balance, BlockNumber> where
+		AccountId: Codec,
+		KittyIndex: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// The kitty with the given index, None for a missing or burned one.
+		fn get_kitty(kitty_id: KittyIndex) -> Option<Kitty>;
+		/// The number of kitty indices handed out so far, burned kitties included.
+		fn kitties_count() -> KittyIndex;
+		/// A page of up to `limit` kitties listed for sale with their prices, after skipping `offset` of them.
+		fn listings(offset: u32, limit: u32) -> Vec<(KittyIndex, Balance)>;
+		/// The kitties owned by `account`.
+		fn kitties_of(account: AccountId) -> Vec<(KittyIndex, Kitty)>;
+		/// Whether `account` owns the kitty.
+		fn is_owner(account: AccountId, kitty_id: KittyIndex) -> bool;
+		/// The DNA of each requested kitty, None for a missing one.
+		fn dnas_of(ids: Vec<KittyIndex>) -> Vec<Option<[u8; 16]>>;
+		/// Whether a kitty with the given DNA has already been minted.
+		fn dna_exists(dna: [u8; 16]) -> bool;
+		/// The number of accounts owning at least one kitty.
+		fn distinct_owners() -> u32;
+		/// The stake the next kitty minted or acquired by `account` reserves.
+		fn current_stake_for(account: AccountId) -> Balance;
+		/// The restrictions currently applying to a kitty.
+		fn kitty_flags(kitty_id: KittyIndex) -> KittyFlags;
+		/// Up to `limit` listed kitties `account` can afford to buy.
+		fn buyable_by(account: AccountId, limit: u32) -> Vec<KittyIndex>;
+		/// The mint block and the leading random seed bytes behind a kitty's DNA.
+		fn kitty_random_source(kitty_id: KittyIndex) -> Option<(BlockNumber, [u8; 8])>;
+		/// Up to `limit` kitties whose trait `selector.0` has the value `selector.1`.
+		fn kitties_with_trait(selector: (u8, u8), limit: u32) -> Vec<KittyIndex>;
+		/// The bred kitties with ancestors, within `depth` generations, owned or created by each of the two accounts.
+		fn shared_lineage(account_a: AccountId, account_b: AccountId, depth: u32) -> Vec<KittyIndex>;
+		/// The parents, generation and birth block of a kitty.
+		fn kitty_metadata(kitty_id: KittyIndex) -> Option<Provenance<KittyIndex, BlockNumber>>;
+		/// The most recent sales of a kitty as (price, block), oldest first.
+		fn last_sales(kitty_id: KittyIndex) -> Vec<(Balance, BlockNumber)>;
+		/// The total price of all kitty sales.
+		fn total_volume() -> Balance;
+		/// The lowest price among the kitties listed for sale, None if none are.
+		fn floor_price() -> Option<Balance>;
+	}
+}
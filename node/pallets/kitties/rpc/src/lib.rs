@@ -0,0 +1,207 @@
+//! RPC interface for the kitties pallet.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_kitties_rpc_runtime_api::{KittiesApi as KittiesRuntimeApi, Kitty, KittyFlags, Provenance};
+
+/// Kitties RPC methods, each optionally at a given block hash.
+#[rpc]
+pub trait KittiesApi<BlockHash, AccountId, KittyIndex, Balance, BlockNumber> {
+	/// The kitty with the given index, null for a missing or burned one.
+	#[rpc(name = "kitties_getKitty")]
+	fn get_kitty(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<Kitty>>;
+
+	/// The number of kitty indices handed out so far, burned kitties included.
+	#[rpc(name = "kitties_kittiesCount")]
+	fn kitties_count(&self, at: Option<BlockHash>) -> Result<KittyIndex>;
+
+	/// A page of up to `limit` kitties listed for sale with their prices, after skipping `offset` of them.
+	#[rpc(name = "kitties_listings")]
+	fn listings(&self, offset: u32, limit: u32, at: Option<BlockHash>) -> Result<Vec<(KittyIndex, Balance)>>;
+
+	/// The kitties owned by `account`.
+	#[rpc(name = "kitties_kittiesOf")]
+	fn kitties_of(&self, account: AccountId, at: Option<BlockHash>) -> Result<Vec<(KittyIndex, Kitty)>>;
+
+	/// Whether `account` owns the kitty.
+	#[rpc(name = "kitties_isOwner")]
+	fn is_owner(&self, account: AccountId, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<bool>;
+
+	/// The DNA of each requested kitty, null for a missing one.
+	#[rpc(name = "kitties_dnasOf")]
+	fn dnas_of(&self, ids: Vec<KittyIndex>, at: Option<BlockHash>) -> Result<Vec<Option<[u8; 16]>>>;
+
+	/// Whether a kitty with the given DNA has already been minted.
+	#[rpc(name = "kitties_dnaExists")]
+	fn dna_exists(&self, dna: [u8; 16], at: Option<BlockHash>) -> Result<bool>;
+
+	/// The number of accounts owning at least one kitty.
+	#[rpc(name = "kitties_distinctOwners")]
+	fn distinct_owners(&self, at: Option<BlockHash>) -> Result<u32>;
+
+	/// The stake the next kitty minted or acquired by `account` reserves.
+	#[rpc(name = "kitties_currentStakeFor")]
+	fn current_stake_for(&self, account: AccountId, at: Option<BlockHash>) -> Result<Balance>;
+
+	/// The restrictions currently applying to a kitty.
+	#[rpc(name = "kitties_kittyFlags")]
+	fn kitty_flags(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<KittyFlags>;
+
+	/// Up to `limit` listed kitties `account` can afford to buy.
+	#[rpc(name = "kitties_buyableBy")]
+	fn buyable_by(&self, account: AccountId, limit: u32, at: Option<BlockHash>) -> Result<Vec<KittyIndex>>;
+
+	/// The mint block and the leading random seed bytes behind a kitty's DNA.
+	#[rpc(name = "kitties_kittyRandomSource")]
+	fn kitty_random_source(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<(BlockNumber, [u8; 8])>>;
+
+	/// Up to `limit` kitties whose trait `selector.0` has the value `selector.1`.
+	#[rpc(name = "kitties_kittiesWithTrait")]
+	fn kitties_with_trait(&self, selector: (u8, u8), limit: u32, at: Option<BlockHash>) -> Result<Vec<KittyIndex>>;
+
+	/// The bred kitties with ancestors, within `depth` generations, owned or created by each of the two accounts.
+	#[rpc(name = "kitties_sharedLineage")]
+	fn shared_lineage(&self, account_a: AccountId, account_b: AccountId, depth: u32, at: Option<BlockHash>) -> Result<Vec<KittyIndex>>;
+
+	/// The parents, generation and birth block of a kitty.
+	#[rpc(name = "kitties_kittyMetadata")]
+	fn kitty_metadata(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<Provenance<KittyIndex, BlockNumber>>>;
+
+	/// The most recent sales of a kitty as (price, block), oldest first.
+	#[rpc(name = "kitties_lastSales")]
+	fn last_sales(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Vec<(Balance, BlockNumber)>>;
+
+	/// The total price of all kitty sales.
+	#[rpc(name = "kitties_totalVolume")]
+	fn total_volume(&self, at: Option<BlockHash>) -> Result<Balance>;
+
+	/// The lowest price among the kitties listed for sale, null if none are.
+	#[rpc(name = "kitties_floorPrice")]
+	fn floor_price(&self, at: Option<BlockHash>) -> Result<Option<Balance>>;
+}
+
+/// The error code returned when the runtime API call fails.
+const RUNTIME_ERROR: i64 = 1;
+
+/// Map a runtime API error to a JSON-RPC error object.
+fn runtime_error(err: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Unable to query the kitties runtime API.".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
+
+/// An implementation of the kitties RPC methods on top of a client.
+pub struct Kitties<C, Block> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<Block>,
+}
+
+impl<C, Block> Kitties<C, Block> {
+	/// Create new `Kitties` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block> Kitties<C, Block> where
+	Block: BlockT,
+	C: HeaderBackend<Block>,
+{
+	// The requested block, or the best block if none is given.
+	fn at(&self, at: Option<Block::Hash>) -> BlockId<Block> {
+		BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash))
+	}
+}
+
+impl<C, Block, AccountId, KittyIndex, Balance, BlockNumber>
+	KittiesApi<<Block as BlockT>::Hash, AccountId, KittyIndex, Balance, BlockNumber> for Kitties<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: KittiesRuntimeApi<Block, AccountId, KittyIndex, Balance, BlockNumber>,
+	AccountId: Codec,
+	KittyIndex: Codec,
+	Balance: Codec,
+	BlockNumber: Codec,
+{
+	fn get_kitty(&self, kitty_id: KittyIndex, at: Option<Block::Hash>) -> Result<Option<Kitty>> {
+		self.client.runtime_api().get_kitty(&self.at(at), kitty_id).map_err(runtime_error)
+	}
+
+	fn kitties_count(&self, at: Option<Block::Hash>) -> Result<KittyIndex> {
+		self.client.runtime_api().kitties_count(&self.at(at)).map_err(runtime_error)
+	}
+
+	fn listings(&self, offset: u32, limit: u32, at: Option<Block::Hash>) -> Result<Vec<(KittyIndex, Balance)>> {
+		self.client.runtime_api().listings(&self.at(at), offset, limit).map_err(runtime_error)
+	}
+
+	fn kitties_of(&self, account: AccountId, at: Option<Block::Hash>) -> Result<Vec<(KittyIndex, Kitty)>> {
+		self.client.runtime_api().kitties_of(&self.at(at), account).map_err(runtime_error)
+	}
+
+	fn is_owner(&self, account: AccountId, kitty_id: KittyIndex, at: Option<Block::Hash>) -> Result<bool> {
+		self.client.runtime_api().is_owner(&self.at(at), account, kitty_id).map_err(runtime_error)
+	}
+
+	fn dnas_of(&self, ids: Vec<KittyIndex>, at: Option<Block::Hash>) -> Result<Vec<Option<[u8; 16]>>> {
+		self.client.runtime_api().dnas_of(&self.at(at), ids).map_err(runtime_error)
+	}
+
+	fn dna_exists(&self, dna: [u8; 16], at: Option<Block::Hash>) -> Result<bool> {
+		self.client.runtime_api().dna_exists(&self.at(at), dna).map_err(runtime_error)
+	}
+
+	fn distinct_owners(&self, at: Option<Block::Hash>) -> Result<u32> {
+		self.client.runtime_api().distinct_owners(&self.at(at)).map_err(runtime_error)
+	}
+
+	fn current_stake_for(&self, account: AccountId, at: Option<Block::Hash>) -> Result<Balance> {
+		self.client.runtime_api().current_stake_for(&self.at(at), account).map_err(runtime_error)
+	}
+
+	fn kitty_flags(&self, kitty_id: KittyIndex, at: Option<Block::Hash>) -> Result<KittyFlags> {
+		self.client.runtime_api().kitty_flags(&self.at(at), kitty_id).map_err(runtime_error)
+	}
+
+	fn buyable_by(&self, account: AccountId, limit: u32, at: Option<Block::Hash>) -> Result<Vec<KittyIndex>> {
+		self.client.runtime_api().buyable_by(&self.at(at), account, limit).map_err(runtime_error)
+	}
+
+	fn kitty_random_source(&self, kitty_id: KittyIndex, at: Option<Block::Hash>) -> Result<Option<(BlockNumber, [u8; 8])>> {
+		self.client.runtime_api().kitty_random_source(&self.at(at), kitty_id).map_err(runtime_error)
+	}
+
+	fn kitties_with_trait(&self, selector: (u8, u8), limit: u32, at: Option<Block::Hash>) -> Result<Vec<KittyIndex>> {
+		self.client.runtime_api().kitties_with_trait(&self.at(at), selector, limit).map_err(runtime_error)
+	}
+
+	fn shared_lineage(&self, account_a: AccountId, account_b: AccountId, depth: u32, at: Option<Block::Hash>) -> Result<Vec<KittyIndex>> {
+		self.client.runtime_api().shared_lineage(&self.at(at), account_a, account_b, depth).map_err(runtime_error)
+	}
+
+	fn kitty_metadata(&self, kitty_id: KittyIndex, at: Option<Block::Hash>) -> Result<Option<Provenance<KittyIndex, BlockNumber>>> {
+		self.client.runtime_api().kitty_metadata(&self.at(at), kitty_id).map_err(runtime_error)
+	}
+
+	fn last_sales(&self, kitty_id: KittyIndex, at: Option<Block::Hash>) -> Result<Vec<(Balance, BlockNumber)>> {
+		self.client.runtime_api().last_sales(&self.at(at), kitty_id).map_err(runtime_error)
+	}
+
+	fn total_volume(&self, at: Option<Block::Hash>) -> Result<Balance> {
+		self.client.runtime_api().total_volume(&self.at(at)).map_err(runtime_error)
+	}
+
+	fn floor_price(&self, at: Option<Block::Hash>) -> Result<Option<Balance>> {
+		self.client.runtime_api().floor_price(&self.at(at)).map_err(runtime_error)
+	}
+}
@@ -0,0 +1,104 @@
+//! Benchmarking setup for pallet-kitties
+
+use super::*;
+
+use frame_system::RawOrigin;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller, impl_benchmark_test_suite};
+use frame_support::traits::Currency;
+use sp_runtime::{Percent, traits::{Bounded, StaticLookup, Zero}};
+#[allow(unused)]
+use crate::Pallet as Kitties;
+
+const SEED: u32 = 0;
+
+// An account with plenty of free balance for stakes and prices.
+fn funded<T: Config>(who: T::AccountId) -> T::AccountId {
+	T::Currency::make_free_balance_be(&who, BalanceOf::<T>::max_value() / 4u32.into());
+	who
+}
+
+// Mint a kitty of the given gender for `owner` through reserve_index() and mint_reserved(),
+// so it is indexed like any other kitty.
+fn mint<T: Config>(owner: &T::AccountId, gender: Gender) -> Result<T::KittyIndex, &'static str> {
+	let kitty_id = Kitties::<T>::kitties_count().unwrap_or_else(Zero::zero);
+	let mut dna = [7u8; 16];
+	dna[15] = match gender {
+		Gender::Male => 0,
+		Gender::Female => 1,
+	};
+	Kitties::<T>::reserve_index(RawOrigin::Signed(owner.clone()).into())?;
+	Kitties::<T>::mint_reserved(RawOrigin::Signed(owner.clone()).into(), kitty_id, dna)?;
+	Ok(kitty_id)
+}
+
+benchmarks! {
+	// The mixed-price buy pays its asset leg through pallet_assets.
+	where_clause { where T: pallet_assets::Config + Config<Assets = pallet_assets::Pallet<T>> }
+
+	create {
+		let caller = funded::<T>(whitelisted_caller());
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert_eq!(Owner::<T>::get(T::KittyIndex::from(0u32)), Some(caller));
+	}
+
+	transfer {
+		let caller = funded::<T>(whitelisted_caller());
+		// A recipient without kitties takes the rebate branch and becomes a new owner.
+		let recipient = funded::<T>(account("recipient", 0, SEED));
+		Kitties::<T>::create(RawOrigin::Signed(caller.clone()).into())?;
+		let kitty_id = T::KittyIndex::from(0u32);
+	}: _(RawOrigin::Signed(caller), recipient.clone(), kitty_id)
+	verify {
+		assert_eq!(Owner::<T>::get(kitty_id), Some(recipient));
+	}
+
+	breed {
+		let caller = funded::<T>(whitelisted_caller());
+		// Both parents belong to another account, so the caller pays two stud fees.
+		let stud_owner = funded::<T>(account("stud_owner", 0, SEED));
+		let father = mint::<T>(&stud_owner, Gender::Male)?;
+		let mother = mint::<T>(&stud_owner, Gender::Female)?;
+		Kitties::<T>::set_breed_price(RawOrigin::Signed(stud_owner.clone()).into(), father, Some(100u32.into()))?;
+		Kitties::<T>::set_breed_price(RawOrigin::Signed(stud_owner).into(), mother, Some(100u32.into()))?;
+	}: _(RawOrigin::Signed(caller.clone()), father, mother)
+	verify {
+		assert_eq!(Owner::<T>::get(T::KittyIndex::from(2u32)), Some(caller));
+	}
+
+	sell {
+		let caller = funded::<T>(whitelisted_caller());
+		Kitties::<T>::create(RawOrigin::Signed(caller.clone()).into())?;
+		let kitty_id = T::KittyIndex::from(0u32);
+		let price: BalanceOf<T> = 100u32.into();
+	}: _(RawOrigin::Signed(caller), kitty_id, Some(price), 0u32.into(), None)
+	verify {
+		assert_eq!(ListForSale::<T>::get(kitty_id), Some(price));
+	}
+
+	buy {
+		let seller = funded::<T>(account("seller", 0, SEED));
+		let caller = funded::<T>(whitelisted_caller());
+		// The platform fee, the royalty to a creator other than the seller and the asset leg
+		// of a mixed price each add a transfer to the worst case.
+		let receiver = funded::<T>(account("receiver", 0, SEED));
+		PlatformFee::<T>::put(Some((Percent::from_percent(10), receiver)));
+		let creator = funded::<T>(account("creator", 0, SEED));
+		Kitties::<T>::create(RawOrigin::Signed(creator.clone()).into())?;
+		let kitty_id = T::KittyIndex::from(0u32);
+		Kitties::<T>::transfer(RawOrigin::Signed(creator).into(), seller.clone(), kitty_id)?;
+		let asset = T::SaleAsset::get();
+		pallet_assets::Pallet::<T>::force_create(RawOrigin::Root.into(), asset, T::Lookup::unlookup(seller.clone()), true, 1u32.into())?;
+		pallet_assets::Pallet::<T>::mint(RawOrigin::Signed(seller.clone()).into(), asset, T::Lookup::unlookup(caller.clone()), 1_000u32.into())?;
+		Kitties::<T>::sell_mixed(RawOrigin::Signed(seller).into(), kitty_id, 100u32.into(), 100u32.into(), 0u32.into())?;
+	}: _(RawOrigin::Signed(caller.clone()), kitty_id)
+	verify {
+		assert_eq!(Owner::<T>::get(kitty_id), Some(caller));
+	}
+}
+
+impl_benchmark_test_suite!(
+	Kitties,
+	crate::mock::new_test_ext(),
+	crate::mock::Test,
+);
@@ -0,0 +1,40 @@
+//! DNA combination rules for breeding, plugged into the pallet through `Config::GeneticsEngine`.
+
+use codec::Encode;
+use frame_support::traits::Get;
+use sp_io::hashing::blake2_256;
+use sp_std::marker::PhantomData;
+
+/// Combines the DNA of two parents into the DNA of their child.
+pub trait CombineDna {
+	/// The child DNA of `dna_1` and `dna_2`. `seed` is drawn from the pallet's Randomness for each breeding.
+	fn combine(dna_1: &[u8; 16], dna_2: &[u8; 16], seed: &[u8; 16]) -> [u8; 16];
+}
+
+/// Takes each bit from the first parent where the seed bit is set, and from the second one elsewhere.
+pub struct BitmaskMixer;
+impl CombineDna for BitmaskMixer {
+	fn combine(dna_1: &[u8; 16], dna_2: &[u8; 16], seed: &[u8; 16]) -> [u8; 16] {
+		let mut new_dna = [0u8; 16];
+		for i in 0..dna_1.len() {
+			new_dna[i] = (seed[i] & dna_1[i]) | (!seed[i] & dna_2[i]);
+		}
+		new_dna
+	}
+}
+
+/// Mixes like BitmaskMixer, then replaces each byte with a random one with a chance of `Rate` in 256.
+pub struct MutatingMixer<Rate>(PhantomData<Rate>);
+impl<Rate: Get<u8>> CombineDna for MutatingMixer<Rate> {
+	fn combine(dna_1: &[u8; 16], dna_2: &[u8; 16], seed: &[u8; 16]) -> [u8; 16] {
+		let mut new_dna = BitmaskMixer::combine(dna_1, dna_2, seed);
+		// The first half decides which bytes mutate, the second half what they become.
+		let mutation = (seed, b"mutation").using_encoded(blake2_256);
+		for i in 0..new_dna.len() {
+			if mutation[i] < Rate::get() {
+				new_dna[i] = mutation[16 + i];
+			}
+		}
+		new_dna
+	}
+}
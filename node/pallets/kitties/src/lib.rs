@@ -8,24 +8,178 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
-// #[cfg(feature = "runtime-benchmarks")]
-// mod benchmarking;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+pub use weights::WeightInfo;
+
+pub mod genetics;
+pub use genetics::CombineDna;
+
+#[cfg(feature = "std")]
+pub mod test_helpers;
 
 #[frame_support::pallet]
 pub mod pallet {
 	use frame_support::{
         dispatch::DispatchResult,
         pallet_prelude::*,
-        traits::{Randomness, Currency, ReservableCurrency}
+        BoundedVec,
+        traits::{Randomness, Currency, ReservableCurrency, NamedReservableCurrency, ExistenceRequirement, BalanceStatus, tokens::{fungibles, nonfungibles}},
+        transactional, PalletId,
     };
 	use frame_system::pallet_prelude::*;
     use codec::{Encode, Decode};
     use sp_io::hashing::blake2_128;
-    use sp_runtime::traits::{AtLeast32BitUnsigned, Bounded};
+    use sp_std::{prelude::*, convert::TryInto};
+    #[cfg(any(feature = "try-runtime", feature = "std"))]
+    use sp_std::collections::btree_map::BTreeMap;
+    use frame_support::storage::with_transaction;
+    use sp_runtime::{Percent, TransactionOutcome, traits::{AtLeast32BitUnsigned, Bounded, AccountIdConversion, CheckedSub, Saturating, Zero}};
+    use super::{CombineDna, WeightInfo};
+    #[cfg(feature = "std")]
+    use serde::{Deserialize, Serialize};
+
+    /// The storage layouts of the pallet, for migrations.
+    #[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+    pub enum Releases {
+        /// A kitty is only its DNA, without a gender or a recorded creator.
+        V1,
+        /// A kitty has a gender, and KittyCreator and TraitIndex cover every kitty.
+        V2,
+        /// Everything the pallet reserves is held under its named reserve.
+        V3,
+    }
+
+    impl Default for Releases {
+        fn default() -> Self {
+            Releases::V1
+        }
+    }
+
+    /// A kitty's gender. Breeding needs one parent of each.
+    #[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+    #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+    pub enum Gender {
+        Male,
+        Female,
+    }
+
+    #[derive(Encode, Decode, RuntimeDebug)]
+    #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+    pub struct Kitty {
+        pub dna: [u8;16],
+        pub gender: Gender,
+    }
+
+    impl Kitty {
+        /// A kitty with the given DNA, its gender taken from the parity of the last DNA byte.
+        pub fn new(dna: [u8; 16]) -> Self {
+            let gender = if dna[15] % 2 == 0 { Gender::Male } else { Gender::Female };
+            Kitty { dna, gender }
+        }
+    }
+    /// The restrictions currently applying to a kitty.
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+    #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+    pub struct KittyFlags {
+        /// A certificate, which can't be transferred, sold or bred.
+        pub soulbound: bool,
+        /// Listed for sale with a price.
+        pub listed: bool,
+        /// Can't breed until its BreedingCooldown is over.
+        pub on_cooldown: bool,
+        /// Up for auction.
+        pub on_auction: bool,
+        /// Just sold, so it can't be listed until its PostBuyLock is over.
+        pub locked: bool,
+    }
 
+    /// An organizational grouping of kitties, owned by one account.
     #[derive(Encode, Decode)]
-    pub struct Kitty(pub [u8;16]);
-    type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+    pub struct Collection<AccountId, Name> {
+        pub owner: AccountId,
+        pub name: Name,
+    }
+    /// A pending offer to swap the proposer's kitty (the storage key) for `their_kitty`,
+    /// with `payment` paid by the proposer to the counterparty on top.
+    #[derive(Encode, Decode)]
+    pub struct Trade<AccountId, KittyIndex, Balance> {
+        pub proposer: AccountId,
+        pub counterparty: AccountId,
+        pub their_kitty: KittyIndex,
+        pub payment: Balance,
+    }
+    /// A breeding waiting in the queue for room under MaxMintsPerBlock.
+    /// The stake and the tip are reserved from `breeder` until it is processed.
+    #[derive(Encode, Decode)]
+    pub struct QueuedBreed<AccountId, KittyIndex, Balance> {
+        pub breeder: AccountId,
+        pub kitty_id_1: KittyIndex,
+        pub kitty_id_2: KittyIndex,
+        pub tip: Balance,
+        pub stake: Balance,
+    }
+    /// Where a kitty comes from. A minted kitty has no parents and is generation 0,
+    /// a bred one is one generation after its older parent.
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+    #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+    pub struct Provenance<KittyIndex, BlockNumber> {
+        pub parents: Option<(KittyIndex, KittyIndex)>,
+        pub generation: u32,
+        pub birth_block: BlockNumber,
+    }
+    /// A running auction of the kitty it is keyed by. The highest bid is reserved from its bidder
+    /// until it is outbid or the auction is settled at `end`.
+    #[derive(Encode, Decode)]
+    pub struct Auction<AccountId, Balance, BlockNumber> {
+        pub seller: AccountId,
+        pub min_bid: Balance,
+        pub highest_bid: Option<(AccountId, Balance)>,
+        pub end: BlockNumber,
+    }
+    /// The human-readable name and optional metadata URI of a kitty. The deposit is reserved from the
+    /// depositor until the name is replaced or cleared, or the kitty is burned.
+    #[derive(Encode, Decode)]
+    pub struct KittyName<AccountId, Balance, Name, Uri> {
+        pub name: Name,
+        pub uri: Option<Uri>,
+        pub depositor: AccountId,
+        pub deposit: Balance,
+    }
+    /// A lease of the kitty it is keyed by, offered by the owner to `tenant` for `rent`.
+    /// Once the tenant accepts it, the tenant holds the breeding rights until `end`, while the owner keeps the kitty.
+    #[derive(Encode, Decode)]
+    pub struct Lease<AccountId, Balance, BlockNumber> {
+        pub tenant: AccountId,
+        pub rent: Balance,
+        pub duration: BlockNumber,
+        /// None while the lease is only offered.
+        pub end: Option<BlockNumber>,
+    }
+    /// A lot of kitties listed together by `seller`, sold as a whole for `price`.
+    #[derive(Encode, Decode)]
+    pub struct Bundle<AccountId, Balance, Kitties> {
+        pub seller: AccountId,
+        pub kitties: Kitties,
+        pub price: Balance,
+    }
+    type TradeOf<T> = Trade<<T as frame_system::Config>::AccountId, <T as Config>::KittyIndex, BalanceOf<T>>;
+    type CollectionOf<T> = Collection<<T as frame_system::Config>::AccountId, BoundedVec<u8, <T as Config>::MaxCollectionNameLen>>;
+    pub type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+    type AssetIdOf<T> = <<T as Config>::Assets as fungibles::Inspect<<T as frame_system::Config>::AccountId>>::AssetId;
+    type QueuedBreedOf<T> = QueuedBreed<<T as frame_system::Config>::AccountId, <T as Config>::KittyIndex, BalanceOf<T>>;
+    type ProvenanceOf<T> = Provenance<<T as Config>::KittyIndex, <T as frame_system::Config>::BlockNumber>;
+    type AuctionOf<T> = Auction<<T as frame_system::Config>::AccountId, BalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
+    type LeaseOf<T> = Lease<<T as frame_system::Config>::AccountId, BalanceOf<T>, <T as frame_system::Config>::BlockNumber>;
+    type BundleOf<T> = Bundle<<T as frame_system::Config>::AccountId, BalanceOf<T>, BoundedVec<<T as Config>::KittyIndex, <T as Config>::MaxBatchSize>>;
+    type KittyNameOf<T> = KittyName<
+        <T as frame_system::Config>::AccountId,
+        BalanceOf<T>,
+        BoundedVec<u8, <T as Config>::MaxKittyNameLen>,
+        BoundedVec<u8, <T as Config>::MaxKittyUriLen>,
+    >;
 
     #[pallet::config]
 	pub trait Config: frame_system::Config {
@@ -33,11 +187,102 @@ pub mod pallet {
         type Randomness: Randomness<Self::Hash, Self::BlockNumber>;
         // Define KittyIndex in Runtime.
         type KittyIndex: Parameter + AtLeast32BitUnsigned + Default + Copy + Bounded;
-        type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
+        // Everything the pallet reserves is held under one named reserve, see reserve_id().
+        type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>
+            + NamedReservableCurrency<Self::AccountId, ReserveIdentifier = [u8; 8]>;
         // Configurable constant for the amount of staking when create a kitty,
         // to avoid the user create a big number of kitties to attract the chain.
         #[pallet::constant]
         type StakeForEachKitty: Get<BalanceOf<Self>>;
+        // The pallet id, used for deriving the account of the referral reward pool.
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
+        // Reward paid from the pool to the referrer of each referred mint.
+        #[pallet::constant]
+        type ReferralReward: Get<BalanceOf<Self>>;
+        // The maximum number of kitties which can be minted in a single block.
+        #[pallet::constant]
+        type MaxMintsPerBlock: Get<u32>;
+        // The reduced stake for a certificate kitty.
+        #[pallet::constant]
+        type CertificateStake: Get<BalanceOf<Self>>;
+        // The maximum length of a collection name.
+        #[pallet::constant]
+        type MaxCollectionNameLen: Get<u32>;
+        // The privileged origin for administrative calls.
+        type AdminOrigin: EnsureOrigin<Self::Origin>;
+        // The number of blocks after a transfer during which the admin can reverse it.
+        #[pallet::constant]
+        type ReversalWindow: Get<Self::BlockNumber>;
+        // The number of blocks a kitty is owned for before it needs renewal. Zero disables expiry.
+        #[pallet::constant]
+        type ExpiryBlocks: Get<Self::BlockNumber>;
+        // The fee paid into the pallet pool for each renewal.
+        #[pallet::constant]
+        type RenewalFee: Get<BalanceOf<Self>>;
+        // The maximum number of kitties in an account's OwnedKitties index.
+        #[pallet::constant]
+        type MaxKittiesOwned: Get<u32>;
+        // The discount on the stake when a kitty is transferred to an account without kitties.
+        #[pallet::constant]
+        type NewOwnerRebate: Get<BalanceOf<Self>>;
+        // The number of blocks over which an account's transfers and sales are counted as flips.
+        #[pallet::constant]
+        type FlipWindow: Get<Self::BlockNumber>;
+        // The number of flips within FlipWindow from which an account's stakes are escalated.
+        #[pallet::constant]
+        type FlipThreshold: Get<u32>;
+        // The multiplier applied to StakeForEachKitty for an account at FlipThreshold.
+        #[pallet::constant]
+        type FlipPenaltyFactor: Get<u32>;
+        // The maximum number of breedings waiting in the queue.
+        #[pallet::constant]
+        type MaxQueuedBreeds: Get<u32>;
+        // The maximum number of auctions ending in the same block.
+        #[pallet::constant]
+        type MaxAuctionsEnding: Get<u32>;
+        // The number of blocks after a sale during which the kitty can't be listed again.
+        #[pallet::constant]
+        type PostBuyLock: Get<Self::BlockNumber>;
+        // The share of every resale price paid to the kitty's creator.
+        #[pallet::constant]
+        type RoyaltyPercent: Get<Percent>;
+        // The number of blocks after breeding before a parent can breed again. Zero disables cooldowns.
+        #[pallet::constant]
+        type BreedingCooldown: Get<Self::BlockNumber>;
+        // The maximum number of items in a single batch call.
+        #[pallet::constant]
+        type MaxBatchSize: Get<u32>;
+        // The maximum length of a kitty name.
+        #[pallet::constant]
+        type MaxKittyNameLen: Get<u32>;
+        // The maximum length of a kitty metadata URI.
+        #[pallet::constant]
+        type MaxKittyUriLen: Get<u32>;
+        // The deposit reserved for each byte of a kitty's name and metadata URI.
+        #[pallet::constant]
+        type NameDepositPerByte: Get<BalanceOf<Self>>;
+        // The maximum number of leases ending in the same block.
+        #[pallet::constant]
+        type MaxLeasesEnding: Get<u32>;
+        // The number of most recent sales recorded for each kitty.
+        #[pallet::constant]
+        type MaxSalesRecorded: Get<u32>;
+        // The number of blocks between requesting a kitty and revealing its DNA.
+        #[pallet::constant]
+        type RevealDelay: Get<Self::BlockNumber>;
+        // The maximum number of requested kitties revealed in the same block.
+        #[pallet::constant]
+        type MaxRevealsPerBlock: Get<u32>;
+        // The rules combining the parents' DNA when breeding, such as genetics::BitmaskMixer.
+        type GeneticsEngine: CombineDna;
+        // The assets paying the asset leg of a mixed price.
+        type Assets: fungibles::Transfer<Self::AccountId, Balance = BalanceOf<Self>>;
+        // The asset in which the asset leg of a mixed price is paid.
+        #[pallet::constant]
+        type SaleAsset: Get<AssetIdOf<Self>>;
+        // Weight information for the extrinsics in this pallet.
+        type WeightInfo: WeightInfo;
 	}
 
 	#[pallet::pallet]
@@ -51,6 +296,108 @@ pub mod pallet {
         KittyCreated(T::AccountId, T::KittyIndex),
         KittyTransferred(T::AccountId, T::AccountId, T::KittyIndex),
         KittyListed(T::AccountId, T::KittyIndex, Option<BalanceOf<T>>),
+        /// A listing is removed without a sale. (owner, kitty)
+        KittyDelisted(T::AccountId, T::KittyIndex),
+        /// A kitty is sold through a listing, an auction or an offer. (seller, buyer, kitty, price)
+        KittySold(T::AccountId, T::AccountId, T::KittyIndex, BalanceOf<T>),
+        /// A kitty is bred. (parent 1, parent 2, child)
+        KittyBred(T::KittyIndex, T::KittyIndex, T::KittyIndex),
+        KittyIndexReserved(T::AccountId, T::KittyIndex),
+        /// Referrer rewarded for a mint by the referred account. (referrer, referred, reward)
+        ReferralRewarded(T::AccountId, T::AccountId, BalanceOf<T>),
+        CertificateDestroyed(T::AccountId, T::KittyIndex),
+        /// A kitty is offered for stud with a fee. (None means the stud listing is removed.)
+        StudListed(T::AccountId, T::KittyIndex, Option<BalanceOf<T>>),
+        /// A stud fee is paid. (breeder, stud owner, stud kitty, fee)
+        StudFeePaid(T::AccountId, T::AccountId, T::KittyIndex, BalanceOf<T>),
+        CollectionCreated(T::AccountId, u32),
+        KittyAddedToCollection(u32, T::KittyIndex),
+        KittyRemovedFromCollection(u32, T::KittyIndex),
+        /// A trade is proposed. (proposer, counterparty, my_kitty, their_kitty, payment)
+        TradeProposed(T::AccountId, T::AccountId, T::KittyIndex, T::KittyIndex, BalanceOf<T>),
+        /// A trade keyed by the proposer's kitty is completed.
+        TradeAccepted(T::KittyIndex),
+        /// A trade keyed by the proposer's kitty is rejected or withdrawn.
+        TradeRejected(T::KittyIndex),
+        /// The account receiving a kitty's released stake is set. (None means the owner itself.)
+        StakePayoutSet(T::AccountId, T::KittyIndex, Option<T::AccountId>),
+        /// A transfer is reversed by the admin. (reversed from, returned to, kitty)
+        TransferReversed(T::AccountId, T::AccountId, T::KittyIndex),
+        /// A kitty is burned. (owner, kitty)
+        KittyBurned(T::AccountId, T::KittyIndex),
+        PlatformFeeSet(Percent, T::AccountId),
+        /// The platform cut of a sale is paid. (receiver, kitty, fee)
+        PlatformFeePaid(T::AccountId, T::KittyIndex, BalanceOf<T>),
+        /// A kitty is renewed. (owner, kitty, new expiry)
+        KittyRenewed(T::AccountId, T::KittyIndex, T::BlockNumber),
+        /// An expired kitty is reclaimed. (new owner, former owner, kitty)
+        KittyReclaimed(T::AccountId, T::AccountId, T::KittyIndex),
+        /// An account bred a number of its kitty pairs at once. (owner, count bred)
+        BredAllCompatible(T::AccountId, u32),
+        /// A breeding is queued with a tip. (breeder, parent, parent, tip)
+        BreedQueued(T::AccountId, T::KittyIndex, T::KittyIndex, BalanceOf<T>),
+        /// A queued breeding failed when processed, and its stake and tip were refunded. (breeder, parent, parent)
+        QueuedBreedFailed(T::AccountId, T::KittyIndex, T::KittyIndex),
+        /// A listed kitty also asks for an amount of SaleAsset. (kitty, asset amount)
+        AssetPriceSet(T::KittyIndex, BalanceOf<T>),
+        /// An account reserves a kitty's stake in place of its owner. (sponsor, owner, kitty)
+        StakeSponsored(T::AccountId, T::AccountId, T::KittyIndex),
+        /// A kitty is put up for auction. (seller, kitty, min bid, end)
+        AuctionStarted(T::AccountId, T::KittyIndex, BalanceOf<T>, T::BlockNumber),
+        /// A bid becomes the highest of an auction. (bidder, kitty, amount)
+        BidPlaced(T::AccountId, T::KittyIndex, BalanceOf<T>),
+        /// An auction ends, with the winner and the winning bid. (None means the kitty stays with the seller.)
+        AuctionSettled(T::KittyIndex, Option<(T::AccountId, BalanceOf<T>)>),
+        /// The creator's royalty on a resale is paid. (creator, kitty, royalty)
+        RoyaltyPaid(T::AccountId, T::KittyIndex, BalanceOf<T>),
+        /// An account adds to its stake pool. (account, amount)
+        StakePoolFunded(T::AccountId, BalanceOf<T>),
+        /// An account takes back part of its stake pool. (account, amount)
+        StakePoolWithdrawn(T::AccountId, BalanceOf<T>),
+        /// A stake the free balance couldn't cover is drawn from the stake pool. (account, amount drawn)
+        StakeToppedUp(T::AccountId, BalanceOf<T>),
+        /// A kitty's DNA is sealed for good. (owner, kitty)
+        DnaSealed(T::AccountId, T::KittyIndex),
+        /// The account approved for a kitty is set. (owner, kitty, delegate; None means the approval is removed.)
+        Approval(T::AccountId, T::KittyIndex, Option<T::AccountId>),
+        /// An operator is allowed or disallowed for all of an owner's kitties. (owner, operator, approved)
+        ApprovalForAll(T::AccountId, T::AccountId, bool),
+        /// An offer is made or raised for a kitty. (bidder, kitty, amount)
+        OfferMade(T::AccountId, T::KittyIndex, BalanceOf<T>),
+        /// An offer is withdrawn and refunded. (bidder, kitty)
+        OfferCancelled(T::AccountId, T::KittyIndex),
+        /// An offer is accepted by the owner. (owner, bidder, kitty, amount)
+        OfferAccepted(T::AccountId, T::AccountId, T::KittyIndex, BalanceOf<T>),
+        /// A kitty is named, or renamed. (owner, kitty, deposit)
+        KittyNamed(T::AccountId, T::KittyIndex, BalanceOf<T>),
+        /// A kitty's name is cleared and its deposit refunded. (owner, kitty)
+        KittyNameCleared(T::AccountId, T::KittyIndex),
+        /// A lease is offered. (owner, tenant, kitty, rent, duration)
+        LeaseOffered(T::AccountId, T::AccountId, T::KittyIndex, BalanceOf<T>, T::BlockNumber),
+        /// A lease is accepted and its rent paid. (tenant, kitty, end)
+        LeaseStarted(T::AccountId, T::KittyIndex, T::BlockNumber),
+        /// A lease, offered or running, is over.
+        LeaseEnded(T::KittyIndex),
+        /// A transfer waits for the recipient to claim it. (owner, recipient, kitty)
+        TransferInitiated(T::AccountId, T::AccountId, T::KittyIndex),
+        /// A pending transfer is cancelled by the owner. (owner, kitty)
+        TransferCancelled(T::AccountId, T::KittyIndex),
+        /// The market is paused, or unpaused.
+        MarketPauseSet(bool),
+        /// A kitty is moved by the admin. (former owner, new owner, kitty)
+        KittyForceTransferred(T::AccountId, T::AccountId, T::KittyIndex),
+        /// A kitty is requested, its DNA revealed in a later block. (requester, kitty, reveal block)
+        KittyRequested(T::AccountId, T::KittyIndex, T::BlockNumber),
+        /// A requested kitty couldn't be minted at its reveal, and the stake is returned. (requester, kitty)
+        KittyRequestFailed(T::AccountId, T::KittyIndex),
+        /// Kitties are listed for sale as one bundle. (seller, bundle, price)
+        BundleListed(T::AccountId, u32, BalanceOf<T>),
+        /// A bundle is sold as a whole. (seller, buyer, bundle, price)
+        BundleSold(T::AccountId, T::AccountId, u32, BalanceOf<T>),
+        /// A kitty is taken out of a bundle by the seller. (bundle, kitty)
+        KittyRemovedFromBundle(u32, T::KittyIndex),
+        /// A bundle is removed, by the seller or because one of its kitties changed hands.
+        BundleCancelled(u32),
 	}
 
     /// Storage for tracking all the kitties
@@ -74,6 +421,279 @@ pub mod pallet {
 	#[pallet::getter(fn owner)]
 	pub type Owner<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<T::AccountId>, ValueQuery>;
 
+    /// Storage for kitty indices reserved ahead of minting.
+    /// The reserving account is recorded until it fills the index with mint_reserved().
+    #[pallet::storage]
+	#[pallet::getter(fn reservations)]
+	pub type Reservations<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<T::AccountId>, ValueQuery>;
+
+    /// Storage for the number of mints each account has referred.
+    #[pallet::storage]
+	#[pallet::getter(fn referral_count)]
+	pub type ReferralCount<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+    /// Storage for the number of kitties minted in the current block, reset in on_initialize.
+    #[pallet::storage]
+	#[pallet::getter(fn mints_this_block)]
+	pub type MintsThisBlock<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+    /// Storage for the part of the seller's stake handed over to the buyer of a listed kitty.
+    #[pallet::storage]
+	#[pallet::getter(fn listing_stake_bonus)]
+	pub type ListingStakeBonus<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, BalanceOf<T>, ValueQuery>;
+
+    /// Storage for soulbound certificate kitties, which can't be transferred, sold or bred.
+    #[pallet::storage]
+	#[pallet::getter(fn is_certificate)]
+	pub type Certificates<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, bool, ValueQuery>;
+
+    /// Storage for kitties offered as studs, with the fee paid to the owner for each breeding.
+    #[pallet::storage]
+	#[pallet::getter(fn stud_listings)]
+	pub type StudListings<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<BalanceOf<T>>, ValueQuery>;
+
+    /// Storage for the next collection id.
+    #[pallet::storage]
+	#[pallet::getter(fn next_collection_id)]
+	pub type NextCollectionId<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+    /// Storage for every collection.
+    #[pallet::storage]
+	#[pallet::getter(fn collections)]
+	pub type Collections<T: Config> = StorageMap<_, Blake2_128Concat, u32, Option<CollectionOf<T>>, ValueQuery>;
+
+    /// Storage for the collection a kitty belongs to. A kitty is in at most one collection.
+    #[pallet::storage]
+	#[pallet::getter(fn kitty_collection)]
+	pub type KittyCollection<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<u32>, ValueQuery>;
+
+    /// Storage for the kitties owned by each account, so wallets can enumerate them.
+    #[pallet::storage]
+	#[pallet::getter(fn owned_kitties)]
+	pub type OwnedKitties<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, BoundedVec<T::KittyIndex, T::MaxKittiesOwned>, ValueQuery>;
+
+    /// Storage for the number of accounts owning at least one kitty.
+    #[pallet::storage]
+	#[pallet::getter(fn distinct_owners)]
+	pub type DistinctOwners<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+    /// Storage for pending trades, keyed by the proposer's kitty.
+    #[pallet::storage]
+	#[pallet::getter(fn trades)]
+	pub type Trades<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<TradeOf<T>>, ValueQuery>;
+
+    /// Storage for the account credited with a kitty's stake when the owner releases it.
+    /// Set by the owner, and cleared when the kitty changes hands.
+    #[pallet::storage]
+	#[pallet::getter(fn stake_payout)]
+	pub type StakePayout<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<T::AccountId>, ValueQuery>;
+
+    /// Storage for the previous owner and block of a kitty's last transfer, for reverse_transfer().
+    #[pallet::storage]
+	#[pallet::getter(fn last_transfer)]
+	pub type LastTransfer<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<(T::AccountId, T::BlockNumber)>, ValueQuery>;
+
+    /// Storage for the platform cut taken from every sale, and its receiver.
+    #[pallet::storage]
+	#[pallet::getter(fn platform_fee)]
+	pub type PlatformFee<T: Config> = StorageValue<_, Option<(Percent, T::AccountId)>, ValueQuery>;
+
+    /// Storage for the mint block and the leading bytes of the random seed behind a kitty's DNA.
+    /// Only kitties with random DNA have one, so anyone can audit how it was drawn.
+    #[pallet::storage]
+	#[pallet::getter(fn kitty_random_source)]
+	pub type KittyRandomSource<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<(T::BlockNumber, [u8; 8])>, ValueQuery>;
+
+    /// Storage for the number of kitties an account transferred or sold, and the block its FlipWindow started.
+    #[pallet::storage]
+	#[pallet::getter(fn flips)]
+	pub type Flips<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, Option<(u32, T::BlockNumber)>, ValueQuery>;
+
+    /// Storage for the block after which a kitty can be reclaimed by anyone, unless renewed.
+    #[pallet::storage]
+	#[pallet::getter(fn kitty_expiry)]
+	pub type KittyExpiry<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<T::BlockNumber>, ValueQuery>;
+
+    /// Storage for the stake reserved for a kitty, when it isn't StakeForEachKitty.
+    #[pallet::storage]
+	#[pallet::getter(fn kitty_stake)]
+	pub type KittyStake<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<BalanceOf<T>>, ValueQuery>;
+
+    /// Storage for the breedings waiting for room under MaxMintsPerBlock, processed highest tip first.
+    #[pallet::storage]
+	#[pallet::getter(fn breed_queue)]
+	pub type BreedQueue<T: Config> = StorageValue<_, BoundedVec<QueuedBreedOf<T>, T::MaxQueuedBreeds>, ValueQuery>;
+
+    /// Storage for the SaleAsset amount a listed kitty asks on top of its ListForSale price.
+    #[pallet::storage]
+	#[pallet::getter(fn listing_asset_price)]
+	pub type ListingAssetPrice<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, BalanceOf<T>, ValueQuery>;
+
+    /// Storage for the kitties having each trait, keyed by (trait, value) as decode_traits() gives them.
+    #[pallet::storage]
+	pub type TraitIndex<T: Config> = StorageDoubleMap<_, Blake2_128Concat, (u8, u8), Blake2_128Concat, T::KittyIndex, (), OptionQuery>;
+
+    /// The account holding a kitty's stake in place of its owner, until the kitty is burned or changes hands.
+    #[pallet::storage]
+	#[pallet::getter(fn stake_sponsor)]
+	pub type StakeSponsor<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<T::AccountId>, ValueQuery>;
+
+    /// The running auctions, keyed by kitty.
+    #[pallet::storage]
+	#[pallet::getter(fn auctions)]
+	pub type Auctions<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<AuctionOf<T>>, ValueQuery>;
+
+    /// The kitties whose auctions are settled at the start of a block.
+    #[pallet::storage]
+	#[pallet::getter(fn auctions_ending)]
+	pub type AuctionsEnding<T: Config> = StorageMap<_, Blake2_128Concat, T::BlockNumber, BoundedVec<T::KittyIndex, T::MaxAuctionsEnding>, ValueQuery>;
+
+    /// The block until which a sold kitty can't be listed again.
+    /// It stays with the kitty through later transfers, so handing it on doesn't lift the lock.
+    #[pallet::storage]
+	#[pallet::getter(fn listing_locked_until)]
+	pub type ListingLockedUntil<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<T::BlockNumber>, ValueQuery>;
+
+    /// The account which minted or bred a kitty, paid RoyaltyPercent of its resales.
+    #[pallet::storage]
+	#[pallet::getter(fn kitty_creator)]
+	pub type KittyCreator<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<T::AccountId>, ValueQuery>;
+
+    /// The parents, generation and birth block of each kitty. Kitties minted before it was added have none.
+    #[pallet::storage]
+	#[pallet::getter(fn kitty_metadata)]
+	pub type KittyMetadata<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<ProvenanceOf<T>>, ValueQuery>;
+
+    /// Whether a kitty's DNA is sealed against any change. Sealing is permanent.
+    #[pallet::storage]
+	#[pallet::getter(fn is_dna_sealed)]
+	pub type DnaSealed<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, bool, ValueQuery>;
+
+    /// The storage layout in use. Chains which predate it are on V1.
+    #[pallet::storage]
+	#[pallet::getter(fn storage_version)]
+	pub type StorageVersion<T: Config> = StorageValue<_, Releases, ValueQuery>;
+
+    /// The balance an account has reserved up front for stakes its free balance can't cover.
+    #[pallet::storage]
+	#[pallet::getter(fn stake_pool)]
+	pub type StakePool<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+
+    /// The account approved to transfer or list a kitty on its owner's behalf, cleared when the kitty changes hands.
+    #[pallet::storage]
+	#[pallet::getter(fn approvals)]
+	pub type Approvals<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<T::AccountId>, ValueQuery>;
+
+    /// The operators allowed to act on all of an owner's kitties, keyed by (owner, operator).
+    #[pallet::storage]
+	pub type OperatorApprovals<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, T::AccountId, bool, ValueQuery>;
+
+    /// The block from which a kitty which was a parent can breed again.
+    #[pallet::storage]
+	#[pallet::getter(fn next_breed_at)]
+	pub type NextBreedAt<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<T::BlockNumber>, ValueQuery>;
+
+    /// The offers made for each kitty, keyed by (kitty, bidder). Each amount is reserved from its bidder.
+    /// Offers stay through changes of owner, until accepted or cancelled.
+    #[pallet::storage]
+	#[pallet::getter(fn offers)]
+	pub type Offers<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::KittyIndex, Blake2_128Concat, T::AccountId, BalanceOf<T>, OptionQuery>;
+
+    /// The name and metadata URI of each named kitty. They stay with the kitty when it changes hands.
+    #[pallet::storage]
+	#[pallet::getter(fn kitty_metadata_of)]
+	pub type KittyMetadataOf<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<KittyNameOf<T>>, ValueQuery>;
+
+    /// The block from which a kitty's listing can't be bought anymore. Listings without one don't expire.
+    #[pallet::storage]
+	#[pallet::getter(fn listing_expiry)]
+	pub type ListingExpiry<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<T::BlockNumber>, ValueQuery>;
+
+    /// The lease of each kitty, offered or running.
+    #[pallet::storage]
+	#[pallet::getter(fn leases)]
+	pub type Leases<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<LeaseOf<T>>, ValueQuery>;
+
+    /// The kitties whose leases end at the start of a block.
+    #[pallet::storage]
+	#[pallet::getter(fn leases_ending)]
+	pub type LeasesEnding<T: Config> = StorageMap<_, Blake2_128Concat, T::BlockNumber, BoundedVec<T::KittyIndex, T::MaxLeasesEnding>, ValueQuery>;
+
+    /// The recipient of each kitty's pending transfer, until claimed or cancelled, or the kitty changes hands.
+    #[pallet::storage]
+	#[pallet::getter(fn pending_transfers)]
+	pub type PendingTransfers<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<T::AccountId>, ValueQuery>;
+
+    /// Whether the admin paused the market.
+    #[pallet::storage]
+	#[pallet::getter(fn is_market_paused)]
+	pub type MarketPaused<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+    /// Storage for the most recent sales of each kitty, oldest first. (price, block)
+    #[pallet::storage]
+	#[pallet::getter(fn last_sales)]
+	pub type LastSales<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, BoundedVec<(BalanceOf<T>, T::BlockNumber), T::MaxSalesRecorded>, ValueQuery>;
+
+    /// Storage for the total price of all kitty sales.
+    #[pallet::storage]
+	#[pallet::getter(fn total_volume)]
+	pub type TotalVolume<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+    /// Storage for the block in which each requested kitty gets its DNA.
+    #[pallet::storage]
+	#[pallet::getter(fn pending_reveal)]
+	pub type PendingReveals<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<T::BlockNumber>, ValueQuery>;
+
+    /// Storage for the requested kitties revealed in each block.
+    #[pallet::storage]
+	#[pallet::getter(fn reveals_due)]
+	pub type RevealsDue<T: Config> = StorageMap<_, Blake2_128Concat, T::BlockNumber, BoundedVec<T::KittyIndex, T::MaxRevealsPerBlock>, ValueQuery>;
+
+    /// Storage for the next bundle id.
+    #[pallet::storage]
+	#[pallet::getter(fn next_bundle_id)]
+	pub type NextBundleId<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+    /// Storage for every bundle listed for sale.
+    #[pallet::storage]
+	#[pallet::getter(fn bundles)]
+	pub type Bundles<T: Config> = StorageMap<_, Blake2_128Concat, u32, Option<BundleOf<T>>, ValueQuery>;
+
+    /// Storage for the bundle a kitty is listed in. A kitty is in at most one bundle.
+    #[pallet::storage]
+	#[pallet::getter(fn kitty_bundle)]
+	pub type KittyBundle<T: Config> = StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<u32>, ValueQuery>;
+
+    #[pallet::genesis_config]
+    pub struct GenesisConfig<T: Config> {
+        /// Kitties minted at genesis, as (owner, DNA). Each owner gets StakeForEachKitty reserved.
+        pub kitties: Vec<(T::AccountId, [u8; 16])>,
+    }
+
+    #[cfg(feature = "std")]
+    impl<T: Config> Default for GenesisConfig<T> {
+        fn default() -> Self {
+            Self { kitties: Vec::new() }
+        }
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+        fn build(&self) {
+            for (owner, dna) in &self.kitties {
+                let kitty_id = Pallet::<T>::next_kitty_id()
+                    .expect("genesis kitties must fit in KittyIndex");
+                T::Currency::reserve_named(&Pallet::<T>::reserve_id(), owner, T::StakeForEachKitty::get())
+                    .expect("genesis kitty owner must have StakeForEachKitty free to reserve");
+                Pallet::<T>::insert_kitty(kitty_id, owner, *dna)
+                    .expect("genesis kitty owner must not exceed MaxKittiesOwned");
+            }
+            // Genesis mints don't count towards the first block's MaxMintsPerBlock.
+            MintsThisBlock::<T>::kill();
+            StorageVersion::<T>::put(Releases::V3);
+        }
+    }
+
 	#[pallet::error]
 	pub enum Error<T> {
         KittiesCountOverflow,
@@ -84,6 +704,119 @@ pub mod pallet {
         NotForSale,
         NotEnoughBalanceForStaking,
         NotEnoughBalanceForBuying,
+        NotReservationOwner,
+        SelfReferral,
+        ReferralPoolExhausted,
+        MintRateLimited,
+        StakeBonusTooHigh,
+        KittyIsCertificate,
+        NotCertificate,
+        NotStud,
+        CollectionNameTooLong,
+        InvalidCollection,
+        NotCollectionOwner,
+        AlreadyInCollection,
+        NotInCollection,
+        TradeWithSelf,
+        NoSuchTrade,
+        NotTradeParty,
+        NoTransferToReverse,
+        ReversalWindowPassed,
+        ExpiryDisabled,
+        NotExpired,
+        ExceedMaxKittiesOwned,
+        KittyAlreadyBurned,
+        BreedQueueFull,
+        SameGenderParents,
+        MixedPaymentFailed,
+        AlreadySponsored,
+        SponsorIsOwner,
+        KittyListedForSale,
+        KittyInAuction,
+        NotInAuction,
+        InvalidAuctionDuration,
+        TooManyAuctionsEnding,
+        AuctionEnded,
+        BidTooLow,
+        PostBuyLockActive,
+        TransferToSelf,
+        InsufficientStakePool,
+        DnaSealed,
+        ApproveToOwner,
+        KittyOnCooldown,
+        NoSuchOffer,
+        BatchTooLarge,
+        NameTooLong,
+        UriTooLong,
+        NoKittyName,
+        InvalidListingExpiry,
+        ListingExpired,
+        KittyLeased,
+        NoSuchLease,
+        NotLeaseParty,
+        LeaseRunning,
+        InvalidLeaseDuration,
+        TooManyLeasesEnding,
+        NoPendingTransfer,
+        NotTransferRecipient,
+        MarketPaused,
+        TooManyReveals,
+        RevealPending,
+        EmptyBundle,
+        KittyInBundle,
+        NoSuchBundle,
+        NotInBundle,
+        PlatformFeeTooHigh,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut weight = T::DbWeight::get().reads(1);
+            if Self::storage_version() == Releases::V1 {
+                weight = weight.saturating_add(Self::migrate_to_v2());
+            }
+            if Self::storage_version() == Releases::V2 {
+                weight = weight.saturating_add(Self::migrate_to_v3());
+            }
+            weight
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<(), &'static str> {
+            if Self::storage_version() == Releases::V1 {
+                // Every kitty must still be in the V1 layout.
+                for kitty_id in Kitties::<T>::iter_keys() {
+                    let raw = frame_support::storage::unhashed::get_raw(&Kitties::<T>::hashed_key_for(kitty_id))
+                        .ok_or("kitty listed but missing")?;
+                    <Option<[u8; 16]> as codec::DecodeAll>::decode_all(&raw).map_err(|_| "kitty not in the V1 layout")?;
+                }
+            }
+            Ok(())
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade() -> Result<(), &'static str> {
+            ensure!(Self::storage_version() == Releases::V3, "storage version not V3");
+            for (kitty_id, kitty) in Kitties::<T>::iter() {
+                let kitty = kitty.ok_or("kitty entry without a kitty")?;
+                ensure!(TraitIndex::<T>::contains_key((0u8, kitty.dna[0]), kitty_id), "kitty missing from TraitIndex");
+            }
+            Self::try_state()
+        }
+
+        fn on_initialize(n: T::BlockNumber) -> Weight {
+            MintsThisBlock::<T>::kill();
+            let processed = Self::process_breed_queue();
+            let settled = Self::settle_auctions(n);
+            let expired = Self::expire_leases(n);
+            let revealed = Self::reveal_kitties(n);
+            T::DbWeight::get().writes(1)
+                .saturating_add(T::WeightInfo::breed().saturating_mul(processed as Weight))
+                .saturating_add(T::WeightInfo::buy().saturating_mul(settled as Weight))
+                .saturating_add(T::WeightInfo::sell().saturating_mul(expired as Weight))
+                .saturating_add(T::WeightInfo::create().saturating_mul(revealed as Weight))
+        }
 	}
 
 	#[pallet::call]
@@ -92,141 +825,2387 @@ pub mod pallet {
         /// Create a kitty with the stake configurated from:
         /// #[pallet::constant]
         ///      type StakeForEachKitty: Get<BalanceOf<Self>>)
-        #[pallet::weight(1_000)]
+        #[pallet::weight(T::WeightInfo::create())]
+        #[transactional]
         pub fn create(origin: OriginFor<T>) -> DispatchResult{
             let who = ensure_signed(origin)?;
 
             let dna = Self::random_value(&who);
-            Self::new_kitty_with_stake(&who, dna)?;
+            let kitty_id = Self::new_kitty_with_stake(&who, dna)?;
+            Self::note_random_source(kitty_id);
 
             Ok(())
         }
 
-        /// Transfer a kitty from owner to another.
-        #[pallet::weight(1_000)]
-        pub fn transfer(origin: OriginFor<T>, new_owner: T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+        /// Mint a kitty owned by `recipient`. The caller funds the stake: it is transferred to the
+        /// recipient and reserved there, so the recipient holds it like for any kitty it owns.
+        #[pallet::weight(T::WeightInfo::create().saturating_add(T::DbWeight::get().reads_writes(2, 2)))]
+        #[transactional]
+        pub fn mint_for(origin: OriginFor<T>, recipient: T::AccountId) -> DispatchResult {
             let who = ensure_signed(origin)?;
-            // Ensure transfer only from the OWNER of kitties.
-            ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
 
-            let stake_amount = T::StakeForEachKitty::get();
-
-            // Staking from new owner and unstaking from the ex-ownder
-            T::Currency::reserve(&new_owner, stake_amount)
+            Self::ensure_can_own(&recipient)?;
+            let stake = Self::current_stake_for(&recipient);
+            T::Currency::transfer(&who, &recipient, stake, ExistenceRequirement::KeepAlive)
                 .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
-            T::Currency::unreserve(&who, stake_amount);
-
-            // Update storage.
-            Owner::<T>::insert(kitty_id, Some(new_owner.clone()));
-            // Emit the event.
-            Self::deposit_event(Event::KittyTransferred(who, new_owner, kitty_id));
+            let dna = Self::random_value(&who);
+            let kitty_id = Self::new_kitty_with_stake(&recipient, dna)?;
+            Self::note_random_source(kitty_id);
 
             Ok(())
         }
 
-        /// Breed a kitty from other 2 kitties (Allow the kitty parents belong to other owners).
-        #[pallet::weight(1_000)]
-        pub fn breed(origin: OriginFor<T>, kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) -> DispatchResult {
+        /// Create a kitty like create(), and pay the referrer ReferralReward from the pallet pool.
+        #[pallet::weight(T::WeightInfo::create().saturating_add(T::DbWeight::get().reads_writes(3, 3)))]
+        #[transactional]
+        pub fn create_referred(origin: OriginFor<T>, referrer: T::AccountId) -> DispatchResult {
             let who = ensure_signed(origin)?;
-            // Ensure the parents are not same.
-            ensure!(kitty_id_1 != kitty_id_2, Error::<T>::SameParentIndex);
-            // Ensure there're the parents in the Storage.
-            let kitty1 = Self::kitties(kitty_id_1).ok_or(Error::<T>::InvalidKittyIndex)?;
-            let kitty2 = Self::kitties(kitty_id_2).ok_or(Error::<T>::InvalidKittyIndex)?;
-            // Breed new kitty from the parents.
-            let dna_1 = kitty1.0;
-            let dna_2 = kitty2.0;
-            let selector = Self::random_value(&who);
-            let mut new_dna = [0u8; 16];
-            for i in 0..dna_1.len() {
-                new_dna[i] = (selector[i] & dna_1[i]) | (!selector[i] & dna_2[i]);
-            }
-            Self::new_kitty_with_stake(&who, new_dna)?;
+            // Ensure nobody refers themselves.
+            ensure!(who != referrer, Error::<T>::SelfReferral);
+
+            let dna = Self::random_value(&who);
+            let kitty_id = Self::new_kitty_with_stake(&who, dna)?;
+            Self::note_random_source(kitty_id);
+
+            let reward = T::ReferralReward::get();
+            T::Currency::transfer(&Self::account_id(), &referrer, reward, ExistenceRequirement::AllowDeath)
+                .map_err(|_| Error::<T>::ReferralPoolExhausted)?;
+            ReferralCount::<T>::mutate(&referrer, |count| *count = count.saturating_add(1));
+
+            Self::deposit_event(Event::ReferralRewarded(referrer, who, reward));
 
             Ok(())
         }
 
-        /// Set a price and list a kitty for sale. (Allow set None which means NOT_FOR_SALE.)
-        #[pallet::weight(1_000)]
-        pub fn sell(origin: OriginFor<T>, kitty_id: T::KittyIndex, price: Option<BalanceOf<T>>) -> DispatchResult {
+        /// Transfer a kitty from owner to another.
+        #[pallet::weight(T::WeightInfo::transfer())]
+        #[transactional]
+        pub fn transfer(origin: OriginFor<T>, new_owner: T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
             let who = ensure_signed(origin)?;
-            // Ensure only the kitty owner can sell it.
+            // Ensure transfer only from the OWNER of kitties.
             ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
-            // Set a price. If the price is None, it means the kitty is not for sale.
-            ListForSale::<T>::mutate_exists(kitty_id, |p| *p = Some(price));
-            // Emit event.
-            Self::deposit_event(Event::KittyListed(who, kitty_id, price));
 
-            Ok(())
+            Self::do_transfer(who, new_owner, kitty_id)
         }
 
-        /// Buy a kitty from its owner.
-        #[pallet::weight(1_000)]
-        pub fn buy(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
-            let buyer = ensure_signed(origin)?;
-            let owner = Owner::<T>::get(kitty_id).unwrap();
-            // Ensure the buyer is not the owner.
-            ensure!(Some(buyer.clone()) != Some(owner.clone()), Error::<T>::BuyerIsOwner);
-            // If the price in the ListForSale is None, the kitty is not for sale.
-            let amount = ListForSale::<T>::get(kitty_id).ok_or(Error::<T>::NotForSale)?;
-            // Check the buyer with enough balance to buy. Ensure the free balance can pay and stake also.
-            let buyer_balance = T::Currency::free_balance(&buyer);
-            let stake_amount = T::StakeForEachKitty::get();
-            ensure!(buyer_balance > (amount + stake_amount), Error::<T>::NotEnoughBalanceForBuying);
-            // Staking for own the kitty.
-            T::Currency::reserve(&buyer, stake_amount)
-                .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
-            // Unstaking from the ex-ownder (the seller).
-			T::Currency::unreserve(&owner, stake_amount);
-            // Transfer the price from buyer to the seller.
-			T::Currency::transfer(&buyer, &owner, amount, frame_support::traits::ExistenceRequirement::KeepAlive)?;
-            // Remove from the List.
-			ListForSale::<T>::remove(kitty_id);
-            // Update the storage with the new owner.
-            Owner::<T>::insert(kitty_id, Some(buyer.clone()));
-            // Emit the event.
-            Self::deposit_event(Event::KittyTransferred(owner, buyer, kitty_id));
+        /// Transfer a kitty on its owner's behalf, as the account approved for it or an operator of the owner.
+        /// The stake moves from the owner to the new owner like for transfer().
+        #[pallet::weight(T::WeightInfo::transfer())]
+        #[transactional]
+        pub fn transfer_from(origin: OriginFor<T>, new_owner: T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let owner = Self::ensure_owner_or_approved(&who, kitty_id)?;
 
-            Ok(())
+            Self::do_transfer(owner, new_owner, kitty_id)
         }
 
-    }
+        /// Offer an owned kitty to `recipient`, who takes it over with claim_transfer(). Unlike transfer(),
+        /// nothing is reserved from the recipient until it claims the kitty. An earlier pending transfer is replaced.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn initiate_transfer(origin: OriginFor<T>, kitty_id: T::KittyIndex, recipient: T::AccountId) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+            ensure!(recipient != who, Error::<T>::TransferToSelf);
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
 
-    // Helper functions.
-    impl<T: Config> Pallet<T> {
-        fn random_value(sender: &T::AccountId) -> [u8; 16] {
-            let payload = (
-                T::Randomness::random_seed(),
-                &sender,
-                <frame_system::Pallet<T>>::extrinsic_index(),
-            );
-            payload.using_encoded(blake2_128)
+            PendingTransfers::<T>::insert(kitty_id, Some(recipient.clone()));
+            Self::deposit_event(Event::TransferInitiated(who, recipient, kitty_id));
+
+            Ok(())
         }
 
-        // Helper function for optimizing the codes from create() and transfer().
-        fn new_kitty_with_stake(owner: &T::AccountId, dna: [u8; 16]) -> DispatchResult {
+        /// Take over a kitty whose transfer to the caller is pending, reserving its stake like transfer() does.
+        #[pallet::weight(T::WeightInfo::transfer())]
+        #[transactional]
+        pub fn claim_transfer(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let recipient = Self::pending_transfers(kitty_id).ok_or(Error::<T>::NoPendingTransfer)?;
+            ensure!(recipient == who, Error::<T>::NotTransferRecipient);
+            let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
 
-            let kitty_id = match Self::kitties_count() {
-                Some(id) => {
-                    ensure!(id != T::KittyIndex::max_value(), Error::<T>::KittiesCountOverflow);
-                    id
-                },
-                None => 0u32.into()
-            };
+            Self::do_transfer(owner, who, kitty_id)
+        }
 
-            let stake = T::StakeForEachKitty::get();
+        /// Cancel the pending transfer of an owned kitty.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn cancel_transfer(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+            ensure!(Self::pending_transfers(kitty_id).is_some(), Error::<T>::NoPendingTransfer);
 
-            T::Currency::reserve(&owner, stake)
-                .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+            PendingTransfers::<T>::remove(kitty_id);
+            Self::deposit_event(Event::TransferCancelled(who, kitty_id));
 
-            Kitties::<T>::insert(kitty_id, Some(Kitty(dna)));
-            Owner::<T>::insert(kitty_id, Some(owner.clone()));
-            KittiesCount::<T>::put(kitty_id + 1u32.into());
+            Ok(())
+        }
 
-            Self::deposit_event(Event::KittyCreated(owner.clone(), kitty_id));
+        /// Approve `delegate` to transfer or list one kitty on the owner's behalf, until the kitty changes hands.
+        /// None removes the approval. The owner or one of its operators may call this.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn approve(origin: OriginFor<T>, kitty_id: T::KittyIndex, delegate: Option<T::AccountId>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+            ensure!(owner == who || Self::is_operator(&owner, &who), Error::<T>::NotOwner);
+            ensure!(delegate.as_ref() != Some(&owner), Error::<T>::ApproveToOwner);
+
+            Approvals::<T>::insert(kitty_id, delegate.clone());
+            Self::deposit_event(Event::Approval(owner, kitty_id, delegate));
 
             Ok(())
         }
 
-   }
+        /// Allow or disallow `operator` to transfer, list and approve all of the caller's kitties,
+        /// including the ones acquired later.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn set_approval_for_all(origin: OriginFor<T>, operator: T::AccountId, approved: bool) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(operator != who, Error::<T>::ApproveToOwner);
+
+            if approved {
+                OperatorApprovals::<T>::insert(&who, &operator, true);
+            } else {
+                OperatorApprovals::<T>::remove(&who, &operator);
+            }
+            Self::deposit_event(Event::ApprovalForAll(who, operator, approved));
+
+            Ok(())
+        }
+
+        /// Breed a kitty from other 2 kitties. A parent owned by another account must be listed as a stud,
+        /// and its stud fee is paid to its owner.
+        #[pallet::weight(T::WeightInfo::breed())]
+        #[transactional]
+        pub fn breed(origin: OriginFor<T>, kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            // Reject an invalid pair before any stud fee check, like queue_breed() does.
+            Self::ensure_breedable(kitty_id_1, kitty_id_2)?;
+
+            Self::pay_stud_fee(&who, kitty_id_1)?;
+            Self::pay_stud_fee(&who, kitty_id_2)?;
+            Self::do_breed(&who, kitty_id_1, kitty_id_2)?;
+
+            Ok(())
+        }
+
+        /// Queue a breeding for when there is room under MaxMintsPerBlock, highest tip first.
+        /// The stake and the tip are reserved now. The tip goes to the pallet pool once the kitty is bred,
+        /// and both are refunded if the breeding fails then.
+        #[pallet::weight(T::WeightInfo::breed())]
+        pub fn queue_breed(origin: OriginFor<T>, kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex, tip: BalanceOf<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_breedable(kitty_id_1, kitty_id_2)?;
+            // The stud fees are paid when the breeding is processed.
+            ensure!(Self::is_owner(&who, kitty_id_1) || Self::stud_listings(kitty_id_1).is_some(), Error::<T>::NotStud);
+            ensure!(Self::is_owner(&who, kitty_id_2) || Self::stud_listings(kitty_id_2).is_some(), Error::<T>::NotStud);
+
+            Self::ensure_can_own(&who)?;
+            let stake = Self::current_stake_for(&who);
+            BreedQueue::<T>::try_mutate(|queue| -> DispatchResult {
+                queue.try_push(QueuedBreed { breeder: who.clone(), kitty_id_1, kitty_id_2, tip, stake })
+                    .map_err(|_| Error::<T>::BreedQueueFull)?;
+                T::Currency::reserve_named(&Self::reserve_id(), &who, stake.saturating_add(tip))
+                    .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::BreedQueued(who, kitty_id_1, kitty_id_2, tip));
+
+            Ok(())
+        }
+
+        /// Set a price and list a kitty for sale. (Allow set None which means NOT_FOR_SALE.)
+        /// The seller may leave stake_bonus of their stake to the buyer, who then reserves that much less.
+        /// With `expires_at`, the listing can't be bought from that block on.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn sell(
+            origin: OriginFor<T>,
+            kitty_id: T::KittyIndex,
+            price: Option<BalanceOf<T>>,
+            stake_bonus: BalanceOf<T>,
+            expires_at: Option<T::BlockNumber>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_sell(who, kitty_id, price, stake_bonus, expires_at)?;
+            ListingAssetPrice::<T>::remove(kitty_id);
+
+            Ok(())
+        }
+
+        /// List a kitty for sale like sell(), for `native_price` plus `asset_price` of SaleAsset.
+        /// The buyer pays both legs to the seller, or neither.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn sell_mixed(
+            origin: OriginFor<T>,
+            kitty_id: T::KittyIndex,
+            native_price: BalanceOf<T>,
+            asset_price: BalanceOf<T>,
+            stake_bonus: BalanceOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_sell(who, kitty_id, Some(native_price), stake_bonus, None)?;
+            ListingAssetPrice::<T>::insert(kitty_id, asset_price);
+            Self::deposit_event(Event::AssetPriceSet(kitty_id, asset_price));
+
+            Ok(())
+        }
+
+        /// Buy a kitty from its owner.
+        #[pallet::weight(T::WeightInfo::buy())]
+        #[transactional]
+        pub fn buy(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let buyer = ensure_signed(origin)?;
+            Self::ensure_market_open()?;
+            let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+            let (amount, stake_bonus, buyer_stake) = Self::buy_terms(&buyer, &owner, kitty_id)?;
+            // Staking for own the kitty.
+            T::Currency::reserve_named(&Self::reserve_id(), &buyer, buyer_stake)
+                .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+            // Unstaking from the ex-ownder (the seller), and moving the bonus to the buyer's reserve.
+            Self::release_stake(&owner, kitty_id, Self::stake_of(kitty_id).saturating_sub(stake_bonus))?;
+            T::Currency::repatriate_reserved_named(&Self::reserve_id(), &owner, &buyer, stake_bonus, BalanceStatus::Reserved)?;
+            // Transfer the platform cut to its receiver, and the rest of the price from buyer to the seller.
+            let mut seller_amount = amount;
+            if let Some((percent, receiver)) = Self::platform_fee() {
+                let fee = percent * amount;
+                T::Currency::transfer(&buyer, &receiver, fee, ExistenceRequirement::KeepAlive)?;
+                seller_amount = seller_amount.saturating_sub(fee);
+                Self::deposit_event(Event::PlatformFeePaid(receiver, kitty_id, fee));
+            }
+            if let Some((creator, royalty)) = Self::royalty_for(kitty_id, &owner, amount) {
+                // Never more than what the platform cut left, so the buyer pays the price only.
+                let royalty = royalty.min(seller_amount);
+                T::Currency::transfer(&buyer, &creator, royalty, ExistenceRequirement::KeepAlive)?;
+                seller_amount = seller_amount.saturating_sub(royalty);
+                Self::deposit_event(Event::RoyaltyPaid(creator, kitty_id, royalty));
+            }
+			T::Currency::transfer(&buyer, &owner, seller_amount, ExistenceRequirement::KeepAlive)?;
+            // Collect the asset leg of a mixed price. A failure rolls the native leg back too.
+            let asset_amount = ListingAssetPrice::<T>::get(kitty_id);
+            if !asset_amount.is_zero() {
+                <T::Assets as fungibles::Transfer<_>>::transfer(T::SaleAsset::get(), &buyer, &owner, asset_amount, false)
+                    .map_err(|_| Error::<T>::MixedPaymentFailed)?;
+            }
+            // Remove from the List.
+			ListForSale::<T>::remove(kitty_id);
+            ListingStakeBonus::<T>::remove(kitty_id);
+            ListingAssetPrice::<T>::remove(kitty_id);
+            ListingExpiry::<T>::remove(kitty_id);
+            // Update the storage with the new owner.
+            Owner::<T>::insert(kitty_id, Some(buyer.clone()));
+            Self::clear_owner_state(kitty_id);
+            Self::set_kitty_stake(kitty_id, buyer_stake.saturating_add(stake_bonus));
+            Self::lock_listing(kitty_id);
+            Self::note_released(&owner, kitty_id);
+            Self::note_acquired(&buyer, kitty_id)?;
+            Self::note_flip(&owner);
+            Self::note_sale(kitty_id, amount);
+            // Emit the events.
+            Self::deposit_event(Event::KittySold(owner.clone(), buyer.clone(), kitty_id, amount));
+            Self::deposit_event(Event::KittyTransferred(owner, buyer, kitty_id));
+
+            Ok(())
+        }
+
+        /// Reserve the next kitty index for a later mint_reserved().
+        /// The stake is reserved up front, so the placeholder is backed like any other kitty.
+        #[pallet::weight(T::WeightInfo::create())]
+        pub fn reserve_index(origin: OriginFor<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_reserve_index(&who)?;
+
+            Ok(())
+        }
+
+        /// Fill a previously reserved index with the given DNA.
+        /// Only the account which reserved the index can fill it.
+        #[pallet::weight(T::WeightInfo::create())]
+        #[transactional]
+        pub fn mint_reserved(origin: OriginFor<T>, kitty_id: T::KittyIndex, dna: [u8; 16]) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let reserver = Self::reservations(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+            // Ensure nobody fills someone else's reservation.
+            ensure!(reserver == who, Error::<T>::NotReservationOwner);
+            // A requested kitty gets its DNA at the reveal only.
+            ensure!(Self::pending_reveal(kitty_id).is_none(), Error::<T>::RevealPending);
+
+            Self::fill_reservation(&who, kitty_id, dna)
+        }
+
+        /// Request a kitty whose DNA is drawn from the randomness of a block RevealDelay blocks
+        /// later, which nobody knows yet. Unlike create(), the DNA can't be ground for by picking
+        /// when to submit. The index and the stake are reserved now, the kitty is minted at the reveal.
+        #[pallet::weight(T::WeightInfo::create())]
+        #[transactional]
+        pub fn request_kitty(origin: OriginFor<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            // At least the next block, whose on_initialize is still to come.
+            let reveal_at = <frame_system::Pallet<T>>::block_number().saturating_add(T::RevealDelay::get().max(1u32.into()));
+            let kitty_id = Self::do_reserve_index(&who)?;
+            RevealsDue::<T>::try_mutate(reveal_at, |due| due.try_push(kitty_id))
+                .map_err(|_| Error::<T>::TooManyReveals)?;
+            PendingReveals::<T>::insert(kitty_id, Some(reveal_at));
+
+            Self::deposit_event(Event::KittyRequested(who, kitty_id, reveal_at));
+
+            Ok(())
+        }
+
+        /// Mint a soulbound certificate kitty with the given DNA and a reduced stake.
+        #[pallet::weight(T::WeightInfo::create())]
+        #[transactional]
+        pub fn mint_certificate(origin: OriginFor<T>, dna: [u8; 16]) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let kitty_id = Self::new_kitty(&who, dna, T::CertificateStake::get())?;
+            Certificates::<T>::insert(kitty_id, true);
+
+            Ok(())
+        }
+
+        /// Destroy a certificate kitty. The only way a certificate ever leaves its owner.
+        #[pallet::weight(T::WeightInfo::transfer())]
+        #[transactional]
+        pub fn destroy_certificate(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+            ensure!(Self::is_certificate(kitty_id), Error::<T>::NotCertificate);
+
+            Self::release_stake(&who, kitty_id, T::CertificateStake::get())?;
+            Self::remove_kitty(&who, kitty_id);
+
+            Self::deposit_event(Event::CertificateDestroyed(who, kitty_id));
+
+            Ok(())
+        }
+
+        /// Offer an owned kitty for others to breed with, for a fee paid to the owner.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn list_stud(origin: OriginFor<T>, kitty_id: T::KittyIndex, fee: BalanceOf<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+
+            StudListings::<T>::insert(kitty_id, Some(fee));
+            Self::deposit_event(Event::StudListed(who, kitty_id, Some(fee)));
+
+            Ok(())
+        }
+
+        /// Set the fee others pay to breed with an owned kitty, or None to stop offering it.
+        /// The same as list_stud() and unlist_stud().
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn set_breed_price(origin: OriginFor<T>, kitty_id: T::KittyIndex, price: Option<BalanceOf<T>>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+
+            StudListings::<T>::insert(kitty_id, price);
+            Self::deposit_event(Event::StudListed(who, kitty_id, price));
+
+            Ok(())
+        }
+
+        /// Stop offering a kitty as a stud.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn unlist_stud(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+            ensure!(Self::stud_listings(kitty_id).is_some(), Error::<T>::NotStud);
+
+            StudListings::<T>::remove(kitty_id);
+            Self::deposit_event(Event::StudListed(who, kitty_id, None));
+
+            Ok(())
+        }
+
+        /// Breed an owned kitty with a listed stud, paying the stud fee to the stud owner.
+        /// The offspring belongs to the caller.
+        #[pallet::weight(T::WeightInfo::breed())]
+        #[transactional]
+        pub fn breed_with_stud(origin: OriginFor<T>, own_kitty: T::KittyIndex, stud_kitty: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Some(who.clone()) == Owner::<T>::get(own_kitty), Error::<T>::NotOwner);
+            let fee = Self::stud_listings(stud_kitty).ok_or(Error::<T>::NotStud)?;
+            let stud_owner = Owner::<T>::get(stud_kitty).ok_or(Error::<T>::InvalidKittyIndex)?;
+            ensure!(Self::active_tenant(own_kitty).is_none() && Self::active_tenant(stud_kitty).is_none(), Error::<T>::KittyLeased);
+
+            if stud_owner != who {
+                T::Currency::transfer(&who, &stud_owner, fee, ExistenceRequirement::KeepAlive)?;
+                Self::deposit_event(Event::StudFeePaid(who.clone(), stud_owner, stud_kitty, fee));
+            }
+            Self::do_breed(&who, own_kitty, stud_kitty)?;
+
+            Ok(())
+        }
+
+        /// Create a named collection for organizing the caller's kitties.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn create_collection(origin: OriginFor<T>, name: Vec<u8>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let name: BoundedVec<u8, T::MaxCollectionNameLen> = name.try_into()
+                .map_err(|_| Error::<T>::CollectionNameTooLong)?;
+
+            let collection_id = Self::next_collection_id();
+            Collections::<T>::insert(collection_id, Some(Collection { owner: who.clone(), name }));
+            NextCollectionId::<T>::put(collection_id.saturating_add(1));
+
+            Self::deposit_event(Event::CollectionCreated(who, collection_id));
+
+            Ok(())
+        }
+
+        /// Add an owned kitty to one of the caller's collections.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn add_to_collection(origin: OriginFor<T>, collection_id: u32, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_collection_owner(&who, collection_id)?;
+            ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+            ensure!(Self::kitty_collection(kitty_id).is_none(), Error::<T>::AlreadyInCollection);
+
+            KittyCollection::<T>::insert(kitty_id, Some(collection_id));
+            Self::deposit_event(Event::KittyAddedToCollection(collection_id, kitty_id));
+
+            Ok(())
+        }
+
+        /// Remove a kitty from one of the caller's collections.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn remove_from_collection(origin: OriginFor<T>, collection_id: u32, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_collection_owner(&who, collection_id)?;
+            ensure!(Self::kitty_collection(kitty_id) == Some(collection_id), Error::<T>::NotInCollection);
+
+            KittyCollection::<T>::remove(kitty_id);
+            Self::deposit_event(Event::KittyRemovedFromCollection(collection_id, kitty_id));
+
+            Ok(())
+        }
+
+        /// Propose swapping my_kitty (plus my_payment) for the counterparty's their_kitty.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn trade(
+            origin: OriginFor<T>,
+            my_kitty: T::KittyIndex,
+            their_kitty: T::KittyIndex,
+            counterparty: T::AccountId,
+            my_payment: BalanceOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(who != counterparty, Error::<T>::TradeWithSelf);
+            ensure!(Self::is_owner(&who, my_kitty), Error::<T>::NotOwner);
+            ensure!(Self::is_owner(&counterparty, their_kitty), Error::<T>::NotOwner);
+            ensure!(!Self::is_certificate(my_kitty) && !Self::is_certificate(their_kitty), Error::<T>::KittyIsCertificate);
+
+            Trades::<T>::insert(my_kitty, Some(Trade {
+                proposer: who.clone(),
+                counterparty: counterparty.clone(),
+                their_kitty,
+                payment: my_payment,
+            }));
+            Self::deposit_event(Event::TradeProposed(who, counterparty, my_kitty, their_kitty, my_payment));
+
+            Ok(())
+        }
+
+        /// Accept the trade proposed for the proposer's kitty, swapping both kitties and paying the payment.
+        #[pallet::weight(T::WeightInfo::transfer().saturating_mul(2))]
+        #[transactional]
+        pub fn accept_trade(origin: OriginFor<T>, proposer_kitty: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let trade = Self::trades(proposer_kitty).ok_or(Error::<T>::NoSuchTrade)?;
+            ensure!(trade.counterparty == who, Error::<T>::NotTradeParty);
+            // Both sides must still own their kitties.
+            ensure!(Self::is_owner(&trade.proposer, proposer_kitty), Error::<T>::NotOwner);
+            ensure!(Self::is_owner(&who, trade.their_kitty), Error::<T>::NotOwner);
+            Self::ensure_not_auctioned(proposer_kitty)?;
+            Self::ensure_not_leased(proposer_kitty)?;
+            Self::ensure_not_auctioned(trade.their_kitty)?;
+            Self::ensure_not_leased(trade.their_kitty)?;
+
+            T::Currency::transfer(&trade.proposer, &who, trade.payment, ExistenceRequirement::KeepAlive)?;
+            // Sponsored stakes go back to their sponsors, as for any kitty changing hands.
+            Self::end_sponsorship(&trade.proposer, proposer_kitty)?;
+            Self::end_sponsorship(&who, trade.their_kitty)?;
+
+            // Each side keeps owning one kitty, so the stakes stay where they are,
+            // and each kitty now stands for the stake its new owner had reserved.
+            let proposer_stake = Self::stake_of(proposer_kitty);
+            let counterparty_stake = Self::stake_of(trade.their_kitty);
+            Trades::<T>::remove(proposer_kitty);
+            Self::hand_over_traded(proposer_kitty, &trade.proposer, &who);
+            Self::hand_over_traded(trade.their_kitty, &who, &trade.proposer);
+            Self::set_kitty_stake(proposer_kitty, counterparty_stake);
+            Self::set_kitty_stake(trade.their_kitty, proposer_stake);
+            // Release both kitties before indexing them, so neither side is over MaxKittiesOwned midway.
+            Self::note_released(&trade.proposer, proposer_kitty);
+            Self::note_released(&who, trade.their_kitty);
+            Self::note_acquired(&who, proposer_kitty)?;
+            Self::note_acquired(&trade.proposer, trade.their_kitty)?;
+            Self::deposit_event(Event::TradeAccepted(proposer_kitty));
+
+            Ok(())
+        }
+
+        /// Reject (as the counterparty) or withdraw (as the proposer) a pending trade.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn reject_trade(origin: OriginFor<T>, proposer_kitty: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let trade = Self::trades(proposer_kitty).ok_or(Error::<T>::NoSuchTrade)?;
+            ensure!(trade.counterparty == who || trade.proposer == who, Error::<T>::NotTradeParty);
+
+            Trades::<T>::remove(proposer_kitty);
+            Self::deposit_event(Event::TradeRejected(proposer_kitty));
+
+            Ok(())
+        }
+
+        /// Set the account credited with the kitty's stake when it is released by a transfer, sale or burn.
+        /// None means the owner itself.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn set_stake_payout(origin: OriginFor<T>, kitty_id: T::KittyIndex, payout: Option<T::AccountId>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+
+            StakePayout::<T>::insert(kitty_id, payout.clone());
+            Self::deposit_event(Event::StakePayoutSet(who, kitty_id, payout));
+
+            Ok(())
+        }
+
+        /// Return a kitty to its previous owner, within ReversalWindow blocks of its last transfer.
+        /// The stake moves back with it.
+        #[pallet::weight(T::WeightInfo::transfer())]
+        #[transactional]
+        pub fn reverse_transfer(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            let (previous_owner, transferred_at) = Self::last_transfer(kitty_id).ok_or(Error::<T>::NoTransferToReverse)?;
+            let now = <frame_system::Pallet<T>>::block_number();
+            ensure!(now <= transferred_at.saturating_add(T::ReversalWindow::get()), Error::<T>::ReversalWindowPassed);
+            let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+            Self::ensure_not_auctioned(kitty_id)?;
+            Self::ensure_not_leased(kitty_id)?;
+
+            let stake = Self::current_stake_for(&previous_owner);
+            T::Currency::reserve_named(&Self::reserve_id(), &previous_owner, stake)
+                .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+            Self::release_stake(&owner, kitty_id, Self::stake_of(kitty_id))?;
+
+            Owner::<T>::insert(kitty_id, Some(previous_owner.clone()));
+            ListForSale::<T>::remove(kitty_id);
+            ListingStakeBonus::<T>::remove(kitty_id);
+            ListingAssetPrice::<T>::remove(kitty_id);
+            ListingExpiry::<T>::remove(kitty_id);
+            Self::clear_owner_state(kitty_id);
+            Self::set_kitty_stake(kitty_id, stake);
+            Self::note_released(&owner, kitty_id);
+            Self::note_acquired(&previous_owner, kitty_id)?;
+
+            Self::deposit_event(Event::TransferReversed(owner, previous_owner, kitty_id));
+
+            Ok(())
+        }
+
+        /// Burn a kitty and mint a new one with fresh DNA for the same owner, carrying the stake over.
+        #[pallet::weight(T::WeightInfo::create())]
+        #[transactional]
+        pub fn reroll(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+            ensure!(!Self::is_dna_sealed(kitty_id), Error::<T>::DnaSealed);
+            Self::ensure_not_auctioned(kitty_id)?;
+            Self::ensure_not_leased(kitty_id)?;
+            let old_dna = Self::kitties(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?.dna;
+            let new_kitty_id = Self::next_kitty_id()?;
+            Self::ensure_mint_allowed()?;
+            let expiry = Self::kitty_expiry(kitty_id);
+            let stake = Self::stake_of(kitty_id);
+            let sponsor = Self::stake_sponsor(kitty_id);
+            let creator = Self::kitty_creator(kitty_id);
+            let metadata = Self::kitty_metadata(kitty_id);
+            let next_breed_at = Self::next_breed_at(kitty_id);
+            let locked_until = Self::listing_locked_until(kitty_id);
+            // Taken out before the burn, so the name and its deposit move to the new kitty.
+            let name = KittyMetadataOf::<T>::take(kitty_id);
+
+            // Burn the old kitty, its stake stays reserved for the new one.
+            Self::remove_kitty(&who, kitty_id);
+            Self::deposit_event(Event::KittyBurned(who.clone(), kitty_id));
+
+            // Mix the old DNA in, so the new one differs even within the same block.
+            let new_dna = (Self::random_value(&who), old_dna).using_encoded(blake2_128);
+            Self::insert_kitty(new_kitty_id, &who, new_dna)?;
+            Self::note_random_source(new_kitty_id);
+            // A reroll is not a renewal.
+            KittyExpiry::<T>::insert(new_kitty_id, expiry);
+            Self::set_kitty_stake(new_kitty_id, stake);
+            StakeSponsor::<T>::insert(new_kitty_id, sponsor);
+            KittyCreator::<T>::insert(new_kitty_id, creator);
+            // The new kitty keeps the provenance of the old one.
+            KittyMetadata::<T>::insert(new_kitty_id, metadata);
+            // Rerolling doesn't skip a cooldown.
+            NextBreedAt::<T>::insert(new_kitty_id, next_breed_at);
+            // Nor the PostBuyLock of a just bought kitty.
+            ListingLockedUntil::<T>::insert(new_kitty_id, locked_until);
+            KittyMetadataOf::<T>::insert(new_kitty_id, name);
+
+            Ok(())
+        }
+
+        /// Pause or unpause the market. While paused, kitties can't be listed, bought, auctioned, bid on or sold
+        /// through offers. Unlisting, and the settlement of auctions already running, keep working.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn set_paused(origin: OriginFor<T>, paused: bool) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+
+            MarketPaused::<T>::put(paused);
+            Self::deposit_event(Event::MarketPauseSet(paused));
+
+            Ok(())
+        }
+
+        /// Move a kitty to `new_owner` on behalf of the admin, for stuck or exploited kitties.
+        /// The stake moves like for transfer(), so `new_owner` must be able to reserve it.
+        #[pallet::weight(T::WeightInfo::transfer())]
+        #[transactional]
+        pub fn force_transfer(origin: OriginFor<T>, kitty_id: T::KittyIndex, new_owner: T::AccountId) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+
+            Self::do_transfer(owner.clone(), new_owner.clone(), kitty_id)?;
+            Self::deposit_event(Event::KittyForceTransferred(owner, new_owner, kitty_id));
+
+            Ok(())
+        }
+
+        /// Remove a kitty's listing on behalf of the admin.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn force_delist(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+            ensure!(ListForSale::<T>::get(kitty_id).is_some(), Error::<T>::NotForSale);
+
+            ListForSale::<T>::remove(kitty_id);
+            ListingStakeBonus::<T>::remove(kitty_id);
+            ListingAssetPrice::<T>::remove(kitty_id);
+            ListingExpiry::<T>::remove(kitty_id);
+            Self::deposit_event(Event::KittyDelisted(owner, kitty_id));
+
+            Ok(())
+        }
+
+        /// Set the platform cut taken from every sale, and the account receiving it.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn set_platform_fee(origin: OriginFor<T>, percent: Percent, receiver: T::AccountId) -> DispatchResult {
+            T::AdminOrigin::ensure_origin(origin)?;
+            // The platform cut and the royalty both come out of the price.
+            ensure!(
+                percent.deconstruct().saturating_add(T::RoyaltyPercent::get().deconstruct()) <= 100,
+                Error::<T>::PlatformFeeTooHigh
+            );
+
+            PlatformFee::<T>::put(Some((percent, receiver.clone())));
+            Self::deposit_event(Event::PlatformFeeSet(percent, receiver));
+
+            Ok(())
+        }
+
+        /// Pay RenewalFee to extend a kitty's expiry by ExpiryBlocks.
+        #[pallet::weight(T::WeightInfo::transfer())]
+        #[transactional]
+        pub fn renew(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+            let expiry = Self::kitty_expiry(kitty_id).ok_or(Error::<T>::ExpiryDisabled)?;
+
+            T::Currency::transfer(&who, &Self::account_id(), T::RenewalFee::get(), ExistenceRequirement::KeepAlive)?;
+            // Renewing early extends from the current expiry.
+            let now = <frame_system::Pallet<T>>::block_number();
+            let new_expiry = expiry.max(now).saturating_add(T::ExpiryBlocks::get());
+            KittyExpiry::<T>::insert(kitty_id, Some(new_expiry));
+
+            Self::deposit_event(Event::KittyRenewed(who, kitty_id, new_expiry));
+
+            Ok(())
+        }
+
+        /// Take over an expired kitty, reserving its stake and releasing the former owner's.
+        #[pallet::weight(T::WeightInfo::transfer())]
+        #[transactional]
+        pub fn reclaim_expired(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+            ensure!(owner != who, Error::<T>::BuyerIsOwner);
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+            let expiry = Self::kitty_expiry(kitty_id).ok_or(Error::<T>::ExpiryDisabled)?;
+            ensure!(<frame_system::Pallet<T>>::block_number() > expiry, Error::<T>::NotExpired);
+            Self::ensure_not_auctioned(kitty_id)?;
+            Self::ensure_not_leased(kitty_id)?;
+
+            let stake = Self::current_stake_for(&who);
+            T::Currency::reserve_named(&Self::reserve_id(), &who, stake)
+                .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+            Self::release_stake(&owner, kitty_id, Self::stake_of(kitty_id))?;
+
+            Owner::<T>::insert(kitty_id, Some(who.clone()));
+            ListForSale::<T>::remove(kitty_id);
+            ListingStakeBonus::<T>::remove(kitty_id);
+            ListingAssetPrice::<T>::remove(kitty_id);
+            ListingExpiry::<T>::remove(kitty_id);
+            KittyExpiry::<T>::insert(kitty_id, Self::new_expiry());
+            Self::clear_owner_state(kitty_id);
+            Self::set_kitty_stake(kitty_id, stake);
+            Self::note_released(&owner, kitty_id);
+            Self::note_acquired(&who, kitty_id)?;
+
+            Self::deposit_event(Event::KittyReclaimed(who, owner, kitty_id));
+
+            Ok(())
+        }
+
+        /// Breed up to `max` distinct pairs of opposite genders among the caller's kitties, each pair at most once,
+        /// stopping early when the stake can't be paid or a cap is reached. Parents on cooldown are skipped.
+        // The pair scan runs over every owned kitty whatever `max` is, checking the cooldowns of each pair.
+        #[pallet::weight({
+            let owned = T::MaxKittiesOwned::get() as Weight;
+            T::DbWeight::get().reads(2).saturating_mul(owned.saturating_mul(owned) / 2)
+                .saturating_add(T::WeightInfo::breed().saturating_mul(*max as Weight))
+        })]
+        #[transactional]
+        pub fn breed_all_compatible(origin: OriginFor<T>, max: u32) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let parents: Vec<(T::KittyIndex, Gender)> = Self::owned_kitties(&who)
+                .into_iter()
+                .filter(|kitty_id| !Self::is_certificate(*kitty_id) && Self::active_tenant(*kitty_id).is_none())
+                .filter_map(|kitty_id| Self::kitties(kitty_id).map(|kitty| (kitty_id, kitty.gender)))
+                .collect();
+            let mut bred = 0u32;
+            'pairs: for (i, (kitty_id_1, gender_1)) in parents.iter().enumerate() {
+                for (kitty_id_2, gender_2) in parents.iter().skip(i + 1) {
+                    if gender_1 == gender_2 || Self::on_cooldown(*kitty_id_1) || Self::on_cooldown(*kitty_id_2) {
+                        continue;
+                    }
+                    if bred >= max || Self::owned_kitties(&who).len() as u32 >= T::MaxKittiesOwned::get() {
+                        break 'pairs;
+                    }
+                    // A failed breeding leaves nothing behind, the earlier ones are kept.
+                    let result = with_transaction(|| match Self::do_breed(&who, *kitty_id_1, *kitty_id_2) {
+                        Ok(_) => TransactionOutcome::Commit(Ok(())),
+                        Err(e) => TransactionOutcome::Rollback(Err(e)),
+                    });
+                    if result.is_err() {
+                        break 'pairs;
+                    }
+                    bred += 1;
+                }
+            }
+
+            Self::deposit_event(Event::BredAllCompatible(who, bred));
+
+            Ok(())
+        }
+
+        /// Burn an owned kitty and get its stake back. The index is never reused.
+        #[pallet::weight(T::WeightInfo::transfer())]
+        #[transactional]
+        pub fn burn(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            // Indices not handed out yet, or reserved but not filled, were never minted.
+            let minted = Self::kitties_count().map_or(false, |count| kitty_id < count)
+                && Self::reservations(kitty_id).is_none();
+            ensure!(minted, Error::<T>::InvalidKittyIndex);
+            ensure!(Self::kitties(kitty_id).is_some(), Error::<T>::KittyAlreadyBurned);
+            ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+            Self::ensure_not_auctioned(kitty_id)?;
+            Self::ensure_not_leased(kitty_id)?;
+
+            Self::release_stake(&who, kitty_id, Self::stake_of(kitty_id))?;
+            Self::remove_kitty(&who, kitty_id);
+
+            Self::deposit_event(Event::KittyBurned(who, kitty_id));
+
+            Ok(())
+        }
+
+        /// Reserve a kitty's stake in place of its owner, whose reserve is released.
+        /// The owner keeps every other right over the kitty, and the stake returns to the sponsor
+        /// once the kitty is burned or changes hands.
+        #[pallet::weight(T::WeightInfo::transfer())]
+        #[transactional]
+        pub fn sponsor_stake(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+            ensure!(owner != who, Error::<T>::SponsorIsOwner);
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+            ensure!(Self::stake_sponsor(kitty_id).is_none(), Error::<T>::AlreadySponsored);
+            // A listed stake bonus would hand the sponsor's reserve to the buyer.
+            ensure!(ListingStakeBonus::<T>::get(kitty_id).is_zero(), Error::<T>::StakeBonusTooHigh);
+
+            let stake = Self::stake_of(kitty_id);
+            T::Currency::reserve_named(&Self::reserve_id(), &who, stake)
+                .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+            T::Currency::unreserve_named(&Self::reserve_id(), &owner, stake);
+            StakeSponsor::<T>::insert(kitty_id, Some(who.clone()));
+
+            Self::deposit_event(Event::StakeSponsored(who, owner, kitty_id));
+
+            Ok(())
+        }
+
+        /// Seal an owned kitty's DNA, so nothing can change it anymore. This can't be undone,
+        /// and the seal stays through transfers and sales.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn seal_dna(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+            ensure!(!Self::is_dna_sealed(kitty_id), Error::<T>::DnaSealed);
+
+            DnaSealed::<T>::insert(kitty_id, true);
+            Self::deposit_event(Event::DnaSealed(who, kitty_id));
+
+            Ok(())
+        }
+
+        /// Reserve `amount` into the caller's stake pool, which covers the stake of a kitty transferred to it
+        /// when its free balance can't.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn fund_stake_pool(origin: OriginFor<T>, amount: BalanceOf<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            T::Currency::reserve_named(&Self::reserve_id(), &who, amount)
+                .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+            StakePool::<T>::mutate(&who, |pool| *pool = pool.saturating_add(amount));
+
+            Self::deposit_event(Event::StakePoolFunded(who, amount));
+
+            Ok(())
+        }
+
+        /// Unreserve `amount` from the caller's stake pool.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn withdraw_stake_pool(origin: OriginFor<T>, amount: BalanceOf<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            StakePool::<T>::try_mutate_exists(&who, |pool| -> DispatchResult {
+                let remaining = pool.unwrap_or_default().checked_sub(&amount).ok_or(Error::<T>::InsufficientStakePool)?;
+                *pool = if remaining.is_zero() { None } else { Some(remaining) };
+                Ok(())
+            })?;
+            T::Currency::unreserve_named(&Self::reserve_id(), &who, amount);
+
+            Self::deposit_event(Event::StakePoolWithdrawn(who, amount));
+
+            Ok(())
+        }
+
+        /// Auction an owned kitty for `duration` blocks, accepting bids from `min_bid`.
+        /// The kitty goes to the highest bidder when the auction ends, and can't be listed for sale meanwhile.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn start_auction(origin: OriginFor<T>, kitty_id: T::KittyIndex, min_bid: BalanceOf<T>, duration: T::BlockNumber) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_market_open()?;
+            ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+            ensure!(ListForSale::<T>::get(kitty_id).is_none() || Self::listing_expired(kitty_id), Error::<T>::KittyListedForSale);
+            Self::ensure_not_auctioned(kitty_id)?;
+            Self::ensure_not_leased(kitty_id)?;
+            Self::ensure_listing_unlocked(kitty_id)?;
+            ensure!(Self::kitty_bundle(kitty_id).is_none(), Error::<T>::KittyInBundle);
+            // Auctions are settled at the start of their end block, so they last at least one block.
+            ensure!(!duration.is_zero(), Error::<T>::InvalidAuctionDuration);
+
+            let end = <frame_system::Pallet<T>>::block_number().saturating_add(duration);
+            AuctionsEnding::<T>::try_mutate(end, |ending| ending.try_push(kitty_id))
+                .map_err(|_| Error::<T>::TooManyAuctionsEnding)?;
+            Auctions::<T>::insert(kitty_id, Some(Auction { seller: who.clone(), min_bid, highest_bid: None, end }));
+
+            Self::deposit_event(Event::AuctionStarted(who, kitty_id, min_bid, end));
+
+            Ok(())
+        }
+
+        /// Bid on an auctioned kitty. The bid is reserved, and the previous highest bid is unreserved.
+        #[pallet::weight(T::WeightInfo::transfer())]
+        #[transactional]
+        pub fn bid(origin: OriginFor<T>, kitty_id: T::KittyIndex, amount: BalanceOf<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_market_open()?;
+            let mut auction = Self::auctions(kitty_id).ok_or(Error::<T>::NotInAuction)?;
+            ensure!(<frame_system::Pallet<T>>::block_number() < auction.end, Error::<T>::AuctionEnded);
+            ensure!(who != auction.seller, Error::<T>::BuyerIsOwner);
+            ensure!(amount >= auction.min_bid, Error::<T>::BidTooLow);
+            Self::ensure_can_own(&who)?;
+
+            if let Some((bidder, highest)) = auction.highest_bid.take() {
+                ensure!(amount > highest, Error::<T>::BidTooLow);
+                T::Currency::unreserve_named(&Self::reserve_id(), &bidder, highest);
+            }
+            T::Currency::reserve_named(&Self::reserve_id(), &who, amount)
+                .map_err(|_| Error::<T>::NotEnoughBalanceForBuying)?;
+            auction.highest_bid = Some((who.clone(), amount));
+            Auctions::<T>::insert(kitty_id, Some(auction));
+
+            Self::deposit_event(Event::BidPlaced(who, kitty_id, amount));
+
+            Ok(())
+        }
+
+        /// Offer to lease an owned kitty to `tenant` for `duration` blocks at `rent`. The lease starts when the
+        /// tenant accepts it. An earlier offer, not accepted yet, is replaced.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn lease(
+            origin: OriginFor<T>,
+            kitty_id: T::KittyIndex,
+            tenant: T::AccountId,
+            rent: BalanceOf<T>,
+            duration: T::BlockNumber,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+            ensure!(tenant != who, Error::<T>::NotLeaseParty);
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+            Self::ensure_not_leased(kitty_id)?;
+            ensure!(Self::kitty_bundle(kitty_id).is_none(), Error::<T>::KittyInBundle);
+            // Leases are ended at the start of their end block, so they last at least one block.
+            ensure!(!duration.is_zero(), Error::<T>::InvalidLeaseDuration);
+
+            Leases::<T>::insert(kitty_id, Some(Lease { tenant: tenant.clone(), rent, duration, end: None }));
+            Self::deposit_event(Event::LeaseOffered(who, tenant, kitty_id, rent, duration));
+
+            Ok(())
+        }
+
+        /// Accept a lease offered to the caller, paying the rent to the owner. The caller holds the kitty's
+        /// breeding rights until the lease ends, while the owner can't transfer, sell or breed it.
+        #[pallet::weight(T::WeightInfo::transfer())]
+        #[transactional]
+        pub fn accept_lease(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let mut lease = Self::leases(kitty_id).ok_or(Error::<T>::NoSuchLease)?;
+            ensure!(lease.tenant == who, Error::<T>::NotLeaseParty);
+            ensure!(lease.end.is_none(), Error::<T>::LeaseRunning);
+            let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+            // A kitty put up for auction or sale, alone or in a bundle, after the offer can't be leased anymore.
+            Self::ensure_not_auctioned(kitty_id)?;
+            ensure!(ListForSale::<T>::get(kitty_id).is_none() || Self::listing_expired(kitty_id), Error::<T>::KittyListedForSale);
+            ensure!(Self::kitty_bundle(kitty_id).is_none(), Error::<T>::KittyInBundle);
+
+            T::Currency::transfer(&who, &owner, lease.rent, ExistenceRequirement::KeepAlive)?;
+            let end = <frame_system::Pallet<T>>::block_number().saturating_add(lease.duration);
+            LeasesEnding::<T>::try_mutate(end, |ending| ending.try_push(kitty_id))
+                .map_err(|_| Error::<T>::TooManyLeasesEnding)?;
+            lease.end = Some(end);
+            Leases::<T>::insert(kitty_id, Some(lease));
+
+            Self::deposit_event(Event::LeaseStarted(who, kitty_id, end));
+
+            Ok(())
+        }
+
+        /// End a kitty's lease. The owner or the tenant can withdraw an offered lease, and the tenant can end
+        /// a running one early, without a refund of the rent. A running lease otherwise ends by itself.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn end_lease(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let lease = Self::leases(kitty_id).ok_or(Error::<T>::NoSuchLease)?;
+            match lease.end {
+                Some(end) => {
+                    ensure!(lease.tenant == who, Error::<T>::LeaseRunning);
+                    LeasesEnding::<T>::mutate(end, |ending| ending.retain(|id| *id != kitty_id));
+                },
+                None => {
+                    ensure!(lease.tenant == who || Self::is_owner(&who, kitty_id), Error::<T>::NotLeaseParty);
+                },
+            }
+
+            Leases::<T>::remove(kitty_id);
+            Self::deposit_event(Event::LeaseEnded(kitty_id));
+
+            Ok(())
+        }
+
+        /// Name an owned kitty, with an optional metadata URI, reserving NameDepositPerByte for each byte of both.
+        /// A name set before is replaced, and its deposit refunded.
+        #[pallet::weight(T::WeightInfo::sell())]
+        #[transactional]
+        pub fn set_name(origin: OriginFor<T>, kitty_id: T::KittyIndex, name: Vec<u8>, uri: Option<Vec<u8>>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+            let bytes = name.len().saturating_add(uri.as_ref().map_or(0, |uri| uri.len()));
+            let name: BoundedVec<u8, T::MaxKittyNameLen> = name.try_into()
+                .map_err(|_| Error::<T>::NameTooLong)?;
+            let uri: Option<BoundedVec<u8, T::MaxKittyUriLen>> = match uri {
+                Some(uri) => Some(uri.try_into().map_err(|_| Error::<T>::UriTooLong)?),
+                None => None,
+            };
+
+            Self::release_name_deposit(kitty_id);
+            let deposit = T::NameDepositPerByte::get().saturating_mul((bytes as u32).into());
+            T::Currency::reserve_named(&Self::reserve_id(), &who, deposit)
+                .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+            KittyMetadataOf::<T>::insert(kitty_id, Some(KittyName { name, uri, depositor: who.clone(), deposit }));
+
+            Self::deposit_event(Event::KittyNamed(who, kitty_id, deposit));
+
+            Ok(())
+        }
+
+        /// Clear an owned kitty's name and metadata URI, refunding the deposit to whoever paid it.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn clear_name(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+            ensure!(Self::kitty_metadata_of(kitty_id).is_some(), Error::<T>::NoKittyName);
+
+            Self::release_name_deposit(kitty_id);
+            Self::deposit_event(Event::KittyNameCleared(who, kitty_id));
+
+            Ok(())
+        }
+
+        /// Create `count` kitties at once, each like create(). Either all of them are created or none.
+        #[pallet::weight(T::WeightInfo::create().saturating_mul(*count as Weight))]
+        #[transactional]
+        pub fn create_batch(origin: OriginFor<T>, count: u32) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(count <= T::MaxBatchSize::get(), Error::<T>::BatchTooLarge);
+
+            for _ in 0..count {
+                let dna = Self::random_value(&who);
+                let kitty_id = Self::new_kitty_with_stake(&who, dna)?;
+                Self::note_random_source(kitty_id);
+            }
+
+            Ok(())
+        }
+
+        /// Transfer several owned kitties at once, each like transfer(), as (kitty, new owner) pairs.
+        /// Either all of them are transferred or none.
+        #[pallet::weight(T::WeightInfo::transfer().saturating_mul(transfers.len() as Weight))]
+        #[transactional]
+        pub fn transfer_batch(origin: OriginFor<T>, transfers: Vec<(T::KittyIndex, T::AccountId)>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(transfers.len() as u32 <= T::MaxBatchSize::get(), Error::<T>::BatchTooLarge);
+
+            for (kitty_id, new_owner) in transfers {
+                ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+                Self::do_transfer(who.clone(), new_owner, kitty_id)?;
+            }
+
+            Ok(())
+        }
+
+        /// List or unlist several kitties at once, each like sell() without a stake bonus, as (kitty, price) pairs.
+        /// Either all of them are listed or none.
+        #[pallet::weight(T::WeightInfo::sell().saturating_mul(listings.len() as Weight))]
+        #[transactional]
+        pub fn sell_batch(origin: OriginFor<T>, listings: Vec<(T::KittyIndex, Option<BalanceOf<T>>)>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(listings.len() as u32 <= T::MaxBatchSize::get(), Error::<T>::BatchTooLarge);
+
+            for (kitty_id, price) in listings {
+                Self::do_sell(who.clone(), kitty_id, price, Zero::zero(), None)?;
+                ListingAssetPrice::<T>::remove(kitty_id);
+            }
+
+            Ok(())
+        }
+
+        /// List several owned kitties for sale as one bundle, bought as a whole for `price`.
+        /// The kitties can't be listed or auctioned on their own meanwhile, and a kitty changing hands
+        /// removes its bundle.
+        #[pallet::weight(T::WeightInfo::sell().saturating_mul(kitty_ids.len() as Weight))]
+        #[transactional]
+        pub fn sell_bundle(origin: OriginFor<T>, kitty_ids: Vec<T::KittyIndex>, price: BalanceOf<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_market_open()?;
+            ensure!(!kitty_ids.is_empty(), Error::<T>::EmptyBundle);
+            let kitties: BoundedVec<T::KittyIndex, T::MaxBatchSize> = kitty_ids.try_into()
+                .map_err(|_| Error::<T>::BatchTooLarge)?;
+
+            let bundle_id = Self::next_bundle_id();
+            for kitty_id in kitties.iter() {
+                let kitty_id = *kitty_id;
+                ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+                ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+                // Also rejects a kitty given twice.
+                ensure!(Self::kitty_bundle(kitty_id).is_none(), Error::<T>::KittyInBundle);
+                ensure!(ListForSale::<T>::get(kitty_id).is_none() || Self::listing_expired(kitty_id), Error::<T>::KittyListedForSale);
+                Self::ensure_not_auctioned(kitty_id)?;
+                Self::ensure_not_leased(kitty_id)?;
+                Self::ensure_listing_unlocked(kitty_id)?;
+                KittyBundle::<T>::insert(kitty_id, Some(bundle_id));
+            }
+            Bundles::<T>::insert(bundle_id, Some(Bundle { seller: who.clone(), kitties, price }));
+            NextBundleId::<T>::put(bundle_id.saturating_add(1));
+
+            Self::deposit_event(Event::BundleListed(who, bundle_id, price));
+
+            Ok(())
+        }
+
+        /// Buy all the kitties of a bundle. Each kitty is sold like through buy(), for an even share of
+        /// the price: the buyer stakes for every one of them, and the seller's stakes are released.
+        /// Either all of them change hands or none.
+        #[pallet::weight(T::WeightInfo::buy().saturating_mul(T::MaxBatchSize::get() as Weight))]
+        #[transactional]
+        pub fn buy_bundle(origin: OriginFor<T>, bundle_id: u32) -> DispatchResult {
+            let buyer = ensure_signed(origin)?;
+            Self::ensure_market_open()?;
+            let bundle = Bundles::<T>::take(bundle_id).ok_or(Error::<T>::NoSuchBundle)?;
+            ensure!(bundle.seller != buyer, Error::<T>::BuyerIsOwner);
+
+            // The price is reserved up front, then split evenly across the kitties, the first one taking
+            // the remainder. Each kitty is sold for its share like after an auction, so the platform cut and
+            // its creator's royalty are taken from that share.
+            T::Currency::reserve_named(&Self::reserve_id(), &buyer, bundle.price)
+                .map_err(|_| Error::<T>::NotEnoughBalanceForBuying)?;
+            let count: BalanceOf<T> = (bundle.kitties.len() as u32).into();
+            let share = bundle.price / count;
+            let mut remainder = bundle.price.saturating_sub(share.saturating_mul(count));
+            for kitty_id in bundle.kitties.iter() {
+                KittyBundle::<T>::remove(kitty_id);
+                Self::hand_over_reserved(*kitty_id, &bundle.seller, &buyer, share.saturating_add(remainder))?;
+                remainder = Zero::zero();
+            }
+            Self::note_flip(&bundle.seller);
+
+            Self::deposit_event(Event::BundleSold(bundle.seller, buyer, bundle_id, bundle.price));
+
+            Ok(())
+        }
+
+        /// Take kitties out of one of the caller's bundles, or all of them if `kitty_ids` is None.
+        /// The price stays the same. A bundle left without kitties is removed.
+        #[pallet::weight(T::WeightInfo::sell().saturating_mul(T::MaxBatchSize::get() as Weight))]
+        #[transactional]
+        pub fn cancel_bundle(origin: OriginFor<T>, bundle_id: u32, kitty_ids: Option<Vec<T::KittyIndex>>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let mut bundle = Self::bundles(bundle_id).ok_or(Error::<T>::NoSuchBundle)?;
+            ensure!(bundle.seller == who, Error::<T>::NotOwner);
+
+            let removed = kitty_ids.unwrap_or_else(|| bundle.kitties.to_vec());
+            for kitty_id in removed {
+                ensure!(Self::kitty_bundle(kitty_id) == Some(bundle_id), Error::<T>::NotInBundle);
+                KittyBundle::<T>::remove(kitty_id);
+                bundle.kitties.retain(|id| *id != kitty_id);
+                Self::deposit_event(Event::KittyRemovedFromBundle(bundle_id, kitty_id));
+            }
+            if bundle.kitties.is_empty() {
+                Bundles::<T>::remove(bundle_id);
+                Self::deposit_event(Event::BundleCancelled(bundle_id));
+            } else {
+                Bundles::<T>::insert(bundle_id, Some(bundle));
+            }
+
+            Ok(())
+        }
+
+        /// Offer `amount` for a kitty, listed for sale or not. The amount is reserved until the offer is
+        /// accepted or cancelled, and replaces any earlier offer of the caller for the kitty.
+        #[pallet::weight(T::WeightInfo::transfer())]
+        #[transactional]
+        pub fn make_offer(origin: OriginFor<T>, kitty_id: T::KittyIndex, amount: BalanceOf<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_market_open()?;
+            let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+            ensure!(owner != who, Error::<T>::BuyerIsOwner);
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+
+            if let Some(previous) = Offers::<T>::get(kitty_id, &who) {
+                T::Currency::unreserve_named(&Self::reserve_id(), &who, previous);
+            }
+            T::Currency::reserve_named(&Self::reserve_id(), &who, amount)
+                .map_err(|_| Error::<T>::NotEnoughBalanceForBuying)?;
+            Offers::<T>::insert(kitty_id, &who, amount);
+
+            Self::deposit_event(Event::OfferMade(who, kitty_id, amount));
+
+            Ok(())
+        }
+
+        /// Withdraw the caller's offer for a kitty, unreserving its amount.
+        #[pallet::weight(T::WeightInfo::sell())]
+        pub fn cancel_offer(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let amount = Offers::<T>::take(kitty_id, &who).ok_or(Error::<T>::NoSuchOffer)?;
+
+            T::Currency::unreserve_named(&Self::reserve_id(), &who, amount);
+            Self::deposit_event(Event::OfferCancelled(who, kitty_id));
+
+            Ok(())
+        }
+
+        /// Sell an owned kitty to `bidder` for its offer. The offer pays the seller like a purchase would,
+        /// and the bidder reserves the stake.
+        #[pallet::weight(T::WeightInfo::buy())]
+        #[transactional]
+        pub fn accept_offer(origin: OriginFor<T>, kitty_id: T::KittyIndex, bidder: T::AccountId) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_market_open()?;
+            ensure!(Self::is_owner(&who, kitty_id), Error::<T>::NotOwner);
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+            Self::ensure_not_auctioned(kitty_id)?;
+            Self::ensure_not_leased(kitty_id)?;
+            let amount = Offers::<T>::take(kitty_id, &bidder).ok_or(Error::<T>::NoSuchOffer)?;
+
+            Self::hand_over_reserved(kitty_id, &who, &bidder, amount)?;
+            Self::note_flip(&who);
+            Self::deposit_event(Event::OfferAccepted(who, bidder, kitty_id, amount));
+
+            Ok(())
+        }
+
+    }
+
+    // Upper bound of kitties visited by dna_exists().
+    const DNA_SCAN_LIMIT: usize = 10_000;
+    // Upper bound of kitties read by a single dnas_of() query.
+    const MAX_DNAS_QUERY: usize = 256;
+    // Upper bound of listings returned by a single listings() query.
+    const MAX_LISTINGS_QUERY: u32 = 256;
+    // Upper bound of kitties visited by shared_lineage().
+    const LINEAGE_SCAN_LIMIT: usize = 1_000;
+    // Upper bound of generations walked up by shared_lineage().
+    const MAX_LINEAGE_DEPTH: u32 = 8;
+    /// The number of traits decode_traits() reads from a DNA: body, eyes, accessory, fur and mouth.
+    pub const TRAIT_COUNT: usize = 5;
+
+    // Helper functions.
+    impl<T: Config> Pallet<T> {
+        /// The account holding the referral reward pool.
+        pub fn account_id() -> T::AccountId {
+            T::PalletId::get().into_account()
+        }
+
+        /// The named reserve holding the stakes, bids and stake pools of this pallet, the PalletId bytes.
+        pub fn reserve_id() -> [u8; 8] {
+            T::PalletId::get().0
+        }
+
+        /// The stake the next kitty minted or acquired by `owner` reserves.
+        /// This is StakeForEachKitty, multiplied by FlipPenaltyFactor while the owner is flipping kitties.
+        pub fn current_stake_for(owner: &T::AccountId) -> BalanceOf<T> {
+            let stake = T::StakeForEachKitty::get();
+            if Self::recent_flips(owner) >= T::FlipThreshold::get() {
+                stake.saturating_mul(T::FlipPenaltyFactor::get().into())
+            } else {
+                stake
+            }
+        }
+
+        /// The number of kitties `who` transferred or sold within the current FlipWindow.
+        pub fn recent_flips(who: &T::AccountId) -> u32 {
+            match Self::flips(who) {
+                Some((count, start)) if <frame_system::Pallet<T>>::block_number() < start.saturating_add(T::FlipWindow::get()) => count,
+                _ => 0,
+            }
+        }
+
+        /// The restrictions currently applying to a kitty, aggregated from their storage.
+        pub fn kitty_flags(kitty_id: T::KittyIndex) -> KittyFlags {
+            KittyFlags {
+                soulbound: Self::is_certificate(kitty_id),
+                listed: ListForSale::<T>::get(kitty_id).is_some() && !Self::listing_expired(kitty_id),
+                on_cooldown: Self::on_cooldown(kitty_id),
+                on_auction: Self::auctions(kitty_id).is_some(),
+                locked: Self::ensure_listing_unlocked(kitty_id).is_err(),
+            }
+        }
+
+        /// Whether `who` owns the kitty. False for a nonexistent kitty.
+        pub fn is_owner(who: &T::AccountId, kitty_id: T::KittyIndex) -> bool {
+            Owner::<T>::get(kitty_id).as_ref() == Some(who)
+        }
+
+        /// The kitties owned by `account`, read from its OwnedKitties index.
+        pub fn kitties_of(account: &T::AccountId) -> Vec<(T::KittyIndex, Kitty)> {
+            Self::owned_kitties(account)
+                .into_iter()
+                .filter_map(|kitty_id| Self::kitties(kitty_id).map(|kitty| (kitty_id, kitty)))
+                .collect()
+        }
+
+        /// The DNA of each requested kitty, None for a missing one.
+        /// Only the first MAX_DNAS_QUERY ids are read.
+        pub fn dnas_of(ids: Vec<T::KittyIndex>) -> Vec<Option<[u8; 16]>> {
+            ids.into_iter()
+                .take(MAX_DNAS_QUERY)
+                .map(|id| Self::kitties(id).map(|kitty| kitty.dna))
+                .collect()
+        }
+
+        /// A page of up to `limit` kitties listed with a price and not expired, with the price, after skipping `offset` of them.
+        /// The order is the storage order, stable while the listings don't change. At most MAX_LISTINGS_QUERY are returned.
+        pub fn listings(offset: u32, limit: u32) -> Vec<(T::KittyIndex, BalanceOf<T>)> {
+            ListForSale::<T>::iter()
+                .filter(|(kitty_id, _)| !Self::listing_expired(*kitty_id))
+                .filter_map(|(kitty_id, price)| price.map(|price| (kitty_id, price)))
+                .skip(offset as usize)
+                .take(limit.min(MAX_LISTINGS_QUERY) as usize)
+                .collect()
+        }
+
+        /// The lowest price among the kitties listed for sale right now, None if none are.
+        pub fn floor_price() -> Option<BalanceOf<T>> {
+            ListForSale::<T>::iter()
+                .filter(|(kitty_id, _)| !Self::listing_expired(*kitty_id))
+                .filter_map(|(_, price)| price)
+                .min()
+        }
+
+        /// Up to `limit` listed kitties which `account` can buy right now.
+        pub fn buyable_by(account: &T::AccountId, limit: u32) -> Vec<T::KittyIndex> {
+            ListForSale::<T>::iter()
+                .filter(|(_, price)| price.is_some())
+                .filter_map(|(kitty_id, _)| {
+                    let owner = Owner::<T>::get(kitty_id)?;
+                    Self::buy_terms(account, &owner, kitty_id).ok()?;
+                    // buy() also collects the asset leg of a mixed price.
+                    let asset_price = ListingAssetPrice::<T>::get(kitty_id);
+                    let asset_balance = <T::Assets as fungibles::Inspect<_>>::reducible_balance(T::SaleAsset::get(), account, false);
+                    if asset_balance < asset_price {
+                        return None;
+                    }
+                    Some(kitty_id)
+                })
+                .take(limit as usize)
+                .collect()
+        }
+
+        /// The trait values of a DNA, one byte each, in the order of TRAIT_COUNT.
+        pub fn decode_traits(dna: &[u8; 16]) -> [u8; TRAIT_COUNT] {
+            let mut traits = [0u8; TRAIT_COUNT];
+            traits.copy_from_slice(&dna[..TRAIT_COUNT]);
+            traits
+        }
+
+        /// Up to `limit` kitties whose trait `selector.0` has the value `selector.1`, read from TraitIndex.
+        pub fn kitties_with_trait(selector: (u8, u8), limit: u32) -> Vec<T::KittyIndex> {
+            TraitIndex::<T>::iter_prefix(selector)
+                .map(|(kitty_id, _)| kitty_id)
+                .take(limit as usize)
+                .collect()
+        }
+
+        /// The parents of a bred kitty.
+        pub fn kitty_parents(kitty_id: T::KittyIndex) -> Option<(T::KittyIndex, T::KittyIndex)> {
+            Self::kitty_metadata(kitty_id).and_then(|metadata| metadata.parents)
+        }
+
+        /// The bred kitties with ancestors owned or created by `account_a` and by `account_b`,
+        /// looking up to `depth` generations back (at most MAX_LINEAGE_DEPTH).
+        /// There is no index of descendants, so this is a scan bounded by LINEAGE_SCAN_LIMIT.
+        pub fn shared_lineage(account_a: &T::AccountId, account_b: &T::AccountId, depth: u32) -> Vec<T::KittyIndex> {
+            let depth = depth.min(MAX_LINEAGE_DEPTH);
+            let related = |who: &T::AccountId, kitty_id: T::KittyIndex| {
+                Self::is_owner(who, kitty_id) || Self::kitty_creator(kitty_id).as_ref() == Some(who)
+            };
+            let mut shared: Vec<T::KittyIndex> = KittyMetadata::<T>::iter()
+                .take(LINEAGE_SCAN_LIMIT)
+                .filter(|(_, metadata)| matches!(metadata, Some(metadata) if metadata.parents.is_some()))
+                .map(|(kitty_id, _)| kitty_id)
+                .filter(|kitty_id| {
+                    let (mut found_a, mut found_b) = (false, false);
+                    let mut generation: Vec<T::KittyIndex> = sp_std::iter::once(*kitty_id).collect();
+                    for _ in 0..depth {
+                        let mut parents = Vec::new();
+                        for (parent_1, parent_2) in generation.iter().filter_map(|id| Self::kitty_parents(*id)) {
+                            parents.push(parent_1);
+                            parents.push(parent_2);
+                        }
+                        for ancestor in parents.iter() {
+                            found_a |= related(account_a, *ancestor);
+                            found_b |= related(account_b, *ancestor);
+                        }
+                        if found_a && found_b {
+                            return true;
+                        }
+                        generation = parents;
+                    }
+                    false
+                })
+                .collect();
+            shared.sort();
+            shared
+        }
+
+        /// Whether a kitty with the given DNA has already been minted.
+        /// There is no DNA index yet, so this is a scan bounded by DNA_SCAN_LIMIT.
+        pub fn dna_exists(dna: [u8; 16]) -> bool {
+            Kitties::<T>::iter_values()
+                .take(DNA_SCAN_LIMIT)
+                .any(|kitty| matches!(kitty, Some(kitty) if kitty.dna == dna))
+        }
+
+        fn random_value(sender: &T::AccountId) -> [u8; 16] {
+            let payload = (
+                T::Randomness::random_seed(),
+                &sender,
+                <frame_system::Pallet<T>>::extrinsic_index(),
+            );
+            payload.using_encoded(blake2_128)
+        }
+
+        // Add a new kitty to TraitIndex under each of its traits.
+        fn index_traits(kitty_id: T::KittyIndex, dna: &[u8; 16]) {
+            for (position, value) in Self::decode_traits(dna).iter().enumerate() {
+                TraitIndex::<T>::insert((position as u8, *value), kitty_id, ());
+            }
+        }
+
+        // Record a new kitty as a generation 0 kitty born in the current block.
+        fn note_birth(kitty_id: T::KittyIndex) {
+            KittyMetadata::<T>::insert(kitty_id, Some(Provenance {
+                parents: None,
+                generation: 0,
+                birth_block: <frame_system::Pallet<T>>::block_number(),
+            }));
+        }
+
+        // Record the randomness random_value() drew from in this block for a new kitty.
+        fn note_random_source(kitty_id: T::KittyIndex) {
+            let (seed, _) = T::Randomness::random_seed();
+            let mut reference = [0u8; 8];
+            for (byte, seed_byte) in reference.iter_mut().zip(seed.as_ref()) {
+                *byte = *seed_byte;
+            }
+            KittyRandomSource::<T>::insert(kitty_id, Some((<frame_system::Pallet<T>>::block_number(), reference)));
+        }
+
+        // The next free kitty index, shared by minting and reserve_index().
+        fn next_kitty_id() -> Result<T::KittyIndex, DispatchError> {
+            match Self::kitties_count() {
+                Some(id) => {
+                    ensure!(id != T::KittyIndex::max_value(), Error::<T>::KittiesCountOverflow);
+                    Ok(id)
+                },
+                None => Ok(0u32.into())
+            }
+        }
+
+        fn ensure_collection_owner(who: &T::AccountId, collection_id: u32) -> DispatchResult {
+            let collection = Self::collections(collection_id).ok_or(Error::<T>::InvalidCollection)?;
+            ensure!(&collection.owner == who, Error::<T>::NotCollectionOwner);
+            Ok(())
+        }
+
+        // Add a kitty to the OwnedKitties index of its new owner,
+        // counting the owner as distinct on its first kitty.
+        fn note_acquired(who: &T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+            OwnedKitties::<T>::try_mutate(who, |owned| -> DispatchResult {
+                owned.try_push(kitty_id).map_err(|_| Error::<T>::ExceedMaxKittiesOwned)?;
+                if owned.len() == 1 {
+                    DistinctOwners::<T>::mutate(|owners| *owners = owners.saturating_add(1));
+                }
+                Ok(())
+            })
+        }
+
+        // Remove a kitty from the OwnedKitties index of its former owner,
+        // which is no longer a distinct owner once the index is empty.
+        fn note_released(who: &T::AccountId, kitty_id: T::KittyIndex) {
+            OwnedKitties::<T>::mutate_exists(who, |owned| {
+                let now_empty = match owned {
+                    Some(kitties) => {
+                        kitties.retain(|id| *id != kitty_id);
+                        kitties.is_empty()
+                    },
+                    None => false,
+                };
+                if now_empty {
+                    *owned = None;
+                    DistinctOwners::<T>::mutate(|owners| *owners = owners.saturating_sub(1));
+                }
+            });
+        }
+
+        // Move a kitty from `owner` to `new_owner`, who reserves its stake while the owner's is released.
+        fn do_transfer(owner: T::AccountId, new_owner: T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+            // A transfer to the owner itself would only round-trip the stake and emit a misleading event.
+            ensure!(new_owner != owner, Error::<T>::TransferToSelf);
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+            Self::ensure_not_auctioned(kitty_id)?;
+            Self::ensure_not_leased(kitty_id)?;
+            Self::ensure_can_own(&new_owner)?;
+
+            // An account without kitties gets NewOwnerRebate off the stake.
+            let stake_amount = if Self::owned_kitties(&new_owner).is_empty() {
+                Self::current_stake_for(&new_owner).saturating_sub(T::NewOwnerRebate::get())
+            } else {
+                Self::current_stake_for(&new_owner)
+            };
+
+            // Staking from new owner and unstaking from the ex-ownder
+            Self::reserve_stake(&new_owner, stake_amount)?;
+            Self::release_stake(&owner, kitty_id, Self::stake_of(kitty_id))?;
+
+            // Update storage.
+            Owner::<T>::insert(kitty_id, Some(new_owner.clone()));
+            ListForSale::<T>::remove(kitty_id);
+            ListingStakeBonus::<T>::remove(kitty_id);
+            ListingAssetPrice::<T>::remove(kitty_id);
+            ListingExpiry::<T>::remove(kitty_id);
+            Self::clear_owner_state(kitty_id);
+            Self::set_kitty_stake(kitty_id, stake_amount);
+            LastTransfer::<T>::insert(kitty_id, Some((owner.clone(), <frame_system::Pallet<T>>::block_number())));
+            Self::note_released(&owner, kitty_id);
+            Self::note_acquired(&new_owner, kitty_id)?;
+            Self::note_flip(&owner);
+            // Emit the event.
+            Self::deposit_event(Event::KittyTransferred(owner, new_owner, kitty_id));
+
+            Ok(())
+        }
+
+        // Whether `operator` may act on all of `owner`'s kitties.
+        fn is_operator(owner: &T::AccountId, operator: &T::AccountId) -> bool {
+            OperatorApprovals::<T>::get(owner, operator)
+        }
+
+        // Ensure `who` is the owner of a kitty, the account approved for it or an operator of the owner.
+        // Returns the owner.
+        fn ensure_owner_or_approved(who: &T::AccountId, kitty_id: T::KittyIndex) -> Result<T::AccountId, DispatchError> {
+            let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::NotOwner)?;
+            let allowed = &owner == who
+                || Self::approvals(kitty_id).as_ref() == Some(who)
+                || Self::is_operator(&owner, who);
+            ensure!(allowed, Error::<T>::NotOwner);
+            Ok(owner)
+        }
+
+        // Move one side of an accepted trade to its new owner.
+        fn hand_over_traded(kitty_id: T::KittyIndex, from: &T::AccountId, to: &T::AccountId) {
+            Owner::<T>::insert(kitty_id, Some(to.clone()));
+            ListForSale::<T>::remove(kitty_id);
+            ListingStakeBonus::<T>::remove(kitty_id);
+            ListingAssetPrice::<T>::remove(kitty_id);
+            ListingExpiry::<T>::remove(kitty_id);
+            Self::clear_owner_state(kitty_id);
+            Self::deposit_event(Event::KittyTransferred(from.clone(), to.clone(), kitty_id));
+        }
+
+        // List a kitty for the native price, or unlist it for None.
+        // `who` is the owner, or acts on the owner's behalf through an approval.
+        fn do_sell(
+            who: T::AccountId,
+            kitty_id: T::KittyIndex,
+            price: Option<BalanceOf<T>>,
+            stake_bonus: BalanceOf<T>,
+            expires_at: Option<T::BlockNumber>,
+        ) -> DispatchResult {
+            // Ensure only the kitty owner, or an account it approved, can sell it.
+            let owner = Self::ensure_owner_or_approved(&who, kitty_id)?;
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+            Self::ensure_not_auctioned(kitty_id)?;
+            Self::ensure_not_leased(kitty_id)?;
+            // Unlisting is always allowed.
+            if price.is_some() {
+                Self::ensure_market_open()?;
+                ensure!(Self::kitty_bundle(kitty_id).is_none(), Error::<T>::KittyInBundle);
+                Self::ensure_listing_unlocked(kitty_id)?;
+            }
+            // The bonus comes out of the stake reserved for this kitty.
+            ensure!(stake_bonus <= Self::owner_held_stake(kitty_id), Error::<T>::StakeBonusTooHigh);
+            if let Some(expiry) = expires_at {
+                ensure!(expiry > <frame_system::Pallet<T>>::block_number(), Error::<T>::InvalidListingExpiry);
+            }
+            // Set a price. If the price is None, it means the kitty is not for sale.
+            ListForSale::<T>::mutate_exists(kitty_id, |p| *p = Some(price));
+            if price.is_some() {
+                ListingStakeBonus::<T>::insert(kitty_id, stake_bonus);
+                ListingExpiry::<T>::insert(kitty_id, expires_at);
+            } else {
+                ListingStakeBonus::<T>::remove(kitty_id);
+                ListingExpiry::<T>::remove(kitty_id);
+            }
+            // Emit event.
+            match price {
+                Some(_) => Self::deposit_event(Event::KittyListed(owner, kitty_id, price)),
+                None => Self::deposit_event(Event::KittyDelisted(owner, kitty_id)),
+            }
+
+            Ok(())
+        }
+
+        // Check the preconditions of buy(), returning the price, the seller's stake bonus and the buyer's own stake.
+        fn buy_terms(buyer: &T::AccountId, owner: &T::AccountId, kitty_id: T::KittyIndex)
+            -> Result<(BalanceOf<T>, BalanceOf<T>, BalanceOf<T>), DispatchError>
+        {
+            // Ensure the buyer is not the owner.
+            ensure!(buyer != owner, Error::<T>::BuyerIsOwner);
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+            Self::ensure_can_own(buyer)?;
+            // If the price in the ListForSale is None, the kitty is not for sale.
+            let amount = ListForSale::<T>::get(kitty_id).ok_or(Error::<T>::NotForSale)?;
+            ensure!(!Self::listing_expired(kitty_id), Error::<T>::ListingExpired);
+            // Check the buyer with enough balance to buy. Ensure the free balance can pay and stake also,
+            // and still stay at the existential deposit afterwards.
+            let buyer_balance = T::Currency::free_balance(buyer);
+            let stake_amount = Self::current_stake_for(buyer);
+            // The seller's stake bonus covers part of the buyer's stake.
+            let stake_bonus = ListingStakeBonus::<T>::get(kitty_id);
+            let buyer_stake = stake_amount.saturating_sub(stake_bonus);
+            let required = amount
+                .saturating_add(buyer_stake)
+                .saturating_add(T::Currency::minimum_balance());
+            ensure!(buyer_balance >= required, Error::<T>::NotEnoughBalanceForBuying);
+            Ok((amount, stake_bonus, buyer_stake))
+        }
+
+        // Release the owner's stake for a kitty, crediting the kitty's stake payout account if set.
+        // A sponsored stake goes back to its sponsor instead.
+        fn release_stake(owner: &T::AccountId, kitty_id: T::KittyIndex, amount: BalanceOf<T>) -> DispatchResult {
+            if let Some(sponsor) = Self::stake_sponsor(kitty_id) {
+                T::Currency::unreserve_named(&Self::reserve_id(), &sponsor, amount);
+                return Ok(());
+            }
+            match Self::stake_payout(kitty_id) {
+                Some(payout) if &payout != owner => {
+                    T::Currency::repatriate_reserved_named(&Self::reserve_id(), owner, &payout, amount, BalanceStatus::Free)?;
+                },
+                _ => {
+                    T::Currency::unreserve_named(&Self::reserve_id(), owner, amount);
+                },
+            }
+            Ok(())
+        }
+
+        // Whether a kitty's listing has passed its expiry block. False for a listing without expiry.
+        fn listing_expired(kitty_id: T::KittyIndex) -> bool {
+            Self::listing_expiry(kitty_id).map_or(false, |expiry| <frame_system::Pallet<T>>::block_number() >= expiry)
+        }
+
+        // Remove a kitty's name, refunding the deposit to its depositor.
+        fn release_name_deposit(kitty_id: T::KittyIndex) {
+            if let Some(name) = KittyMetadataOf::<T>::take(kitty_id) {
+                T::Currency::unreserve_named(&Self::reserve_id(), &name.depositor, name.deposit);
+            }
+        }
+
+        // Remove a kitty from storage for good. The caller settles its stake.
+        fn remove_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex) {
+            if let Some(kitty) = Kitties::<T>::take(kitty_id) {
+                for (position, value) in Self::decode_traits(&kitty.dna).iter().enumerate() {
+                    TraitIndex::<T>::remove((position as u8, *value), kitty_id);
+                }
+            }
+            Owner::<T>::remove(kitty_id);
+            ListForSale::<T>::remove(kitty_id);
+            ListingStakeBonus::<T>::remove(kitty_id);
+            ListingAssetPrice::<T>::remove(kitty_id);
+            ListingExpiry::<T>::remove(kitty_id);
+            Certificates::<T>::remove(kitty_id);
+            KittyExpiry::<T>::remove(kitty_id);
+            KittyRandomSource::<T>::remove(kitty_id);
+            ListingLockedUntil::<T>::remove(kitty_id);
+            KittyCreator::<T>::remove(kitty_id);
+            KittyMetadata::<T>::remove(kitty_id);
+            DnaSealed::<T>::remove(kitty_id);
+            NextBreedAt::<T>::remove(kitty_id);
+            LastSales::<T>::remove(kitty_id);
+            // Open offers can't be accepted anymore, so the bidders get their funds back.
+            for (bidder, amount) in Offers::<T>::drain_prefix(kitty_id) {
+                T::Currency::unreserve_named(&Self::reserve_id(), &bidder, amount);
+                Self::deposit_event(Event::OfferCancelled(bidder, kitty_id));
+            }
+            Self::release_name_deposit(kitty_id);
+            Self::clear_owner_state(kitty_id);
+            Self::note_released(owner, kitty_id);
+        }
+
+        // The expiry of a kitty minted or renewed now, None if expiry is disabled.
+        fn new_expiry() -> Option<T::BlockNumber> {
+            let expiry_blocks = T::ExpiryBlocks::get();
+            if expiry_blocks.is_zero() {
+                None
+            } else {
+                Some(<frame_system::Pallet<T>>::block_number().saturating_add(expiry_blocks))
+            }
+        }
+
+        // Breed queued kitties, highest tip first, while MaxMintsPerBlock allows. Returns the number processed.
+        fn process_breed_queue() -> u32 {
+            let mut queue = BreedQueue::<T>::take().into_inner();
+            // A stable sort keeps equal tips in queueing order.
+            queue.sort_by(|a, b| b.tip.cmp(&a.tip));
+            let mut processed = 0;
+            while !queue.is_empty() && Self::mints_this_block() < T::MaxMintsPerBlock::get() {
+                let breed = queue.remove(0);
+                T::Currency::unreserve_named(&Self::reserve_id(), &breed.breeder, breed.stake.saturating_add(breed.tip));
+                let result = with_transaction(|| {
+                    let bred = Self::pay_stud_fee(&breed.breeder, breed.kitty_id_1)
+                        .and_then(|_| Self::pay_stud_fee(&breed.breeder, breed.kitty_id_2))
+                        .and_then(|_| Self::do_breed(&breed.breeder, breed.kitty_id_1, breed.kitty_id_2))
+                        .and_then(|_| T::Currency::transfer(&breed.breeder, &Self::account_id(), breed.tip, ExistenceRequirement::KeepAlive));
+                    match bred {
+                        Ok(()) => TransactionOutcome::Commit(Ok(())),
+                        Err(e) => TransactionOutcome::Rollback(Err(e)),
+                    }
+                });
+                if result.is_err() {
+                    Self::deposit_event(Event::QueuedBreedFailed(breed.breeder, breed.kitty_id_1, breed.kitty_id_2));
+                }
+                processed += 1;
+            }
+            // The queue only got shorter, so it still fits.
+            let queue: BoundedVec<QueuedBreedOf<T>, T::MaxQueuedBreeds> = queue.try_into().unwrap_or_default();
+            BreedQueue::<T>::put(queue);
+            processed
+        }
+
+        // Count a transfer or sale by `who`, starting a new FlipWindow once the last one is over.
+        fn note_flip(who: &T::AccountId) {
+            let count = Self::recent_flips(who);
+            let start = match Self::flips(who) {
+                Some((_, start)) if count > 0 => start,
+                _ => <frame_system::Pallet<T>>::block_number(),
+            };
+            Flips::<T>::insert(who, Some((count.saturating_add(1), start)));
+        }
+
+        // Record a sale in the kitty's history, dropping the oldest one once full, and in the total volume.
+        fn note_sale(kitty_id: T::KittyIndex, price: BalanceOf<T>) {
+            let now = <frame_system::Pallet<T>>::block_number();
+            LastSales::<T>::mutate(kitty_id, |sales| {
+                let mut kept = sales.clone().into_inner();
+                if kept.len() as u32 >= T::MaxSalesRecorded::get() && !kept.is_empty() {
+                    kept.remove(0);
+                }
+                kept.push((price, now));
+                *sales = kept.try_into().unwrap_or_default();
+            });
+            TotalVolume::<T>::mutate(|volume| *volume = volume.saturating_add(price));
+        }
+
+        // Reserve the next kitty index for `who`, with the stake a kitty of `who` currently takes.
+        fn do_reserve_index(who: &T::AccountId) -> Result<T::KittyIndex, DispatchError> {
+            let kitty_id = Self::next_kitty_id()?;
+            Self::ensure_mint_allowed()?;
+            Self::ensure_can_own(who)?;
+            let stake = Self::current_stake_for(who);
+            T::Currency::reserve_named(&Self::reserve_id(), who, stake)
+                .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+
+            Reservations::<T>::insert(kitty_id, Some(who.clone()));
+            Self::set_kitty_stake(kitty_id, stake);
+            KittiesCount::<T>::put(kitty_id + 1u32.into());
+            MintsThisBlock::<T>::mutate(|count| *count += 1);
+
+            Self::deposit_event(Event::KittyIndexReserved(who.clone(), kitty_id));
+
+            Ok(kitty_id)
+        }
+
+        // Fill a reserved index with a kitty of the given DNA, owned by the reserving account.
+        fn fill_reservation(who: &T::AccountId, kitty_id: T::KittyIndex, dna: [u8; 16]) -> DispatchResult {
+            Self::note_acquired(who, kitty_id)?;
+            Reservations::<T>::remove(kitty_id);
+            Kitties::<T>::insert(kitty_id, Some(Kitty::new(dna)));
+            Self::index_traits(kitty_id, &dna);
+            Self::note_birth(kitty_id);
+            Owner::<T>::insert(kitty_id, Some(who.clone()));
+            KittyExpiry::<T>::insert(kitty_id, Self::new_expiry());
+            KittyCreator::<T>::insert(kitty_id, Some(who.clone()));
+
+            Self::deposit_event(Event::KittyCreated(who.clone(), kitty_id));
+
+            Ok(())
+        }
+
+        // Mint the kitties requested for block `n`, with DNA drawn from this block's randomness.
+        // A kitty the requester can't own anymore is dropped, and its stake returned.
+        fn reveal_kitties(n: T::BlockNumber) -> u32 {
+            let due = RevealsDue::<T>::take(n);
+            for kitty_id in due.iter() {
+                let kitty_id = *kitty_id;
+                PendingReveals::<T>::remove(kitty_id);
+                let who = match Self::reservations(kitty_id) {
+                    Some(who) => who,
+                    None => continue,
+                };
+                let (seed, _) = T::Randomness::random(&(b"kitties/reveal", kitty_id).encode());
+                let dna = (seed, &who, kitty_id).using_encoded(blake2_128);
+                match Self::fill_reservation(&who, kitty_id, dna) {
+                    Ok(()) => Self::note_random_source(kitty_id),
+                    Err(_) => {
+                        Reservations::<T>::remove(kitty_id);
+                        T::Currency::unreserve_named(&Self::reserve_id(), &who, Self::stake_of(kitty_id));
+                        Self::deposit_event(Event::KittyRequestFailed(who, kitty_id));
+                    },
+                }
+            }
+            due.len() as u32
+        }
+
+        // The stake reserved by the owner for a kitty.
+        fn stake_of(kitty_id: T::KittyIndex) -> BalanceOf<T> {
+            Self::kitty_stake(kitty_id).unwrap_or_else(T::StakeForEachKitty::get)
+        }
+
+        // The part of a kitty's stake reserved by the owner itself rather than a sponsor.
+        fn owner_held_stake(kitty_id: T::KittyIndex) -> BalanceOf<T> {
+            if Self::stake_sponsor(kitty_id).is_some() {
+                Zero::zero()
+            } else {
+                Self::stake_of(kitty_id)
+            }
+        }
+
+        // Return a sponsored stake to its sponsor, the owner reserving it again.
+        fn end_sponsorship(owner: &T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+            if let Some(sponsor) = StakeSponsor::<T>::take(kitty_id) {
+                let stake = Self::stake_of(kitty_id);
+                T::Currency::reserve_named(&Self::reserve_id(), owner, stake)
+                    .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+                T::Currency::unreserve_named(&Self::reserve_id(), &sponsor, stake);
+            }
+            Ok(())
+        }
+
+        // Record the stake reserved by the owner for a kitty, when it differs from StakeForEachKitty.
+        fn set_kitty_stake(kitty_id: T::KittyIndex, stake: BalanceOf<T>) {
+            if stake == T::StakeForEachKitty::get() {
+                KittyStake::<T>::remove(kitty_id);
+            } else {
+                KittyStake::<T>::insert(kitty_id, Some(stake));
+            }
+        }
+
+        // Clear the per-owner state of a kitty which changed hands.
+        fn clear_owner_state(kitty_id: T::KittyIndex) {
+            KittyStake::<T>::remove(kitty_id);
+            StakePayout::<T>::remove(kitty_id);
+            StakeSponsor::<T>::remove(kitty_id);
+            StudListings::<T>::remove(kitty_id);
+            KittyCollection::<T>::remove(kitty_id);
+            Trades::<T>::remove(kitty_id);
+            LastTransfer::<T>::remove(kitty_id);
+            Approvals::<T>::remove(kitty_id);
+            // Only an offered lease can be left when the kitty changes hands.
+            Leases::<T>::remove(kitty_id);
+            PendingTransfers::<T>::remove(kitty_id);
+            Self::dissolve_bundle_of(kitty_id);
+        }
+
+        // Remove the bundle a kitty is listed in, so a buyer never gets less than it was offered.
+        fn dissolve_bundle_of(kitty_id: T::KittyIndex) {
+            if let Some(bundle_id) = KittyBundle::<T>::take(kitty_id) {
+                if let Some(bundle) = Bundles::<T>::take(bundle_id) {
+                    for kitty_id in bundle.kitties.iter() {
+                        KittyBundle::<T>::remove(kitty_id);
+                    }
+                    Self::deposit_event(Event::BundleCancelled(bundle_id));
+                }
+            }
+        }
+
+        // Ensure `who` can take one more kitty within MaxKittiesOwned, before anything is reserved for it.
+        fn ensure_can_own(who: &T::AccountId) -> DispatchResult {
+            ensure!((Self::owned_kitties(who).len() as u32) < T::MaxKittiesOwned::get(), Error::<T>::ExceedMaxKittiesOwned);
+            Ok(())
+        }
+
+        // Translate every V1 kitty into the V2 layout. Its gender follows its DNA like for a new kitty,
+        // its current owner is taken as its creator, and OwnedKitties and DistinctOwners are rebuilt
+        // from Owner since V1 never kept them. Returns the weight used.
+        fn migrate_to_v2() -> Weight {
+            let mut translated: Weight = 0;
+            Kitties::<T>::translate::<Option<[u8; 16]>, _>(|kitty_id, dna| {
+                translated += 1;
+                let dna = dna?;
+                if let Some(owner) = Owner::<T>::get(kitty_id) {
+                    KittyCreator::<T>::mutate(kitty_id, |creator| {
+                        if creator.is_none() {
+                            *creator = Some(owner);
+                        }
+                    });
+                }
+                Self::index_traits(kitty_id, &dna);
+                Some(Some(Kitty::new(dna)))
+            });
+            let cleared = OwnedKitties::<T>::drain().count() as Weight;
+            DistinctOwners::<T>::kill();
+            let mut indexed: Weight = 0;
+            for (kitty_id, owner) in Owner::<T>::iter() {
+                indexed += 1;
+                if let Some(owner) = owner {
+                    // An account over MaxKittiesOwned keeps its kitties, only the ones past the bound go unlisted.
+                    let _ = Self::note_acquired(&owner, kitty_id);
+                }
+            }
+            StorageVersion::<T>::put(Releases::V2);
+            T::DbWeight::get().reads_writes(
+                translated.saturating_mul(3).saturating_add(cleared).saturating_add(indexed.saturating_mul(3)).saturating_add(1),
+                translated.saturating_mul(2 + TRAIT_COUNT as Weight).saturating_add(cleared).saturating_add(indexed.saturating_mul(2)).saturating_add(2),
+            )
+        }
+
+        // Move everything the pallet reserved anonymously before V3 into its named reserve:
+        // kitty and placeholder stakes, stake pools, queued breedings and highest bids. Returns the weight used.
+        fn migrate_to_v3() -> Weight {
+            let mut holds: Vec<(T::AccountId, BalanceOf<T>)> = Vec::new();
+            for (kitty_id, owner) in Owner::<T>::iter() {
+                let owner = match owner {
+                    Some(owner) => owner,
+                    None => continue,
+                };
+                let holder = Self::stake_sponsor(kitty_id).unwrap_or(owner);
+                let stake = if Self::is_certificate(kitty_id) { T::CertificateStake::get() } else { Self::stake_of(kitty_id) };
+                holds.push((holder, stake));
+            }
+            for (kitty_id, reserver) in Reservations::<T>::iter() {
+                if let Some(reserver) = reserver {
+                    holds.push((reserver, Self::stake_of(kitty_id)));
+                }
+            }
+            for (who, amount) in StakePool::<T>::iter() {
+                holds.push((who, amount));
+            }
+            for breed in Self::breed_queue().into_iter() {
+                holds.push((breed.breeder, breed.stake.saturating_add(breed.tip)));
+            }
+            for (_, auction) in Auctions::<T>::iter() {
+                if let Some((bidder, amount)) = auction.and_then(|auction| auction.highest_bid) {
+                    holds.push((bidder, amount));
+                }
+            }
+            let moved = holds.len() as Weight;
+            for (who, amount) in holds {
+                // Only what was actually reserved is moved.
+                let missing = T::Currency::unreserve(&who, amount);
+                let _ = T::Currency::reserve_named(&Self::reserve_id(), &who, amount.saturating_sub(missing));
+            }
+            StorageVersion::<T>::put(Releases::V3);
+            T::DbWeight::get().reads_writes(moved.saturating_mul(3).saturating_add(1), moved.saturating_mul(2).saturating_add(1))
+        }
+
+        /// Check the invariants tying the pallet's storage together: every kitty is below KittiesCount and
+        /// indexed under exactly one owner, listings, auctions, leases and bundles only refer to existing
+        /// kitties, and the named reserve of each account covers the stakes, bids and offers it holds.
+        /// Run by post_upgrade, and usable from tests and mocks.
+        #[cfg(any(feature = "try-runtime", feature = "std"))]
+        pub fn try_state() -> Result<(), &'static str> {
+            let count = Self::kitties_count().unwrap_or_else(Zero::zero);
+            let mut holds: BTreeMap<T::AccountId, BalanceOf<T>> = BTreeMap::new();
+            let mut hold = |who: T::AccountId, amount: BalanceOf<T>| {
+                let held = holds.entry(who).or_insert_with(Zero::zero);
+                *held = held.saturating_add(amount);
+            };
+
+            let mut kitties = 0u32;
+            for (kitty_id, kitty) in Kitties::<T>::iter() {
+                ensure!(kitty.is_some(), "kitty entry without a kitty");
+                ensure!(kitty_id < count, "kitty at or above KittiesCount");
+                let owner = Self::owner(kitty_id).ok_or("kitty without an owner")?;
+                ensure!(Self::owned_kitties(&owner).contains(&kitty_id), "kitty missing from its owner's kitties");
+                let holder = Self::stake_sponsor(kitty_id).unwrap_or(owner);
+                let stake = if Self::is_certificate(kitty_id) { T::CertificateStake::get() } else { Self::stake_of(kitty_id) };
+                hold(holder, stake);
+                kitties += 1;
+            }
+            for kitty_id in Owner::<T>::iter_keys() {
+                ensure!(Kitties::<T>::contains_key(kitty_id), "owner of a missing kitty");
+            }
+            let (mut indexed, mut owners) = (0u32, 0u32);
+            for (owner, owned) in OwnedKitties::<T>::iter() {
+                for kitty_id in owned.iter() {
+                    ensure!(Self::owner(kitty_id) == Some(owner.clone()), "kitty indexed under another account");
+                }
+                indexed += owned.len() as u32;
+                owners += 1;
+            }
+            ensure!(indexed == kitties, "kitties indexed more than once");
+            ensure!(owners == Self::distinct_owners(), "DistinctOwners out of sync");
+
+            for (kitty_id, reserver) in Reservations::<T>::iter() {
+                let reserver = reserver.ok_or("reservation without an account")?;
+                ensure!(kitty_id < count, "reservation at or above KittiesCount");
+                ensure!(!Kitties::<T>::contains_key(kitty_id), "reserved index already minted");
+                hold(reserver, Self::stake_of(kitty_id));
+            }
+            for (who, amount) in StakePool::<T>::iter() {
+                hold(who, amount);
+            }
+            for breed in Self::breed_queue().into_iter() {
+                hold(breed.breeder, breed.stake.saturating_add(breed.tip));
+            }
+            for (kitty_id, bidder, amount) in Offers::<T>::iter() {
+                ensure!(Kitties::<T>::contains_key(kitty_id), "offer for a missing kitty");
+                hold(bidder, amount);
+            }
+            for (kitty_id, auction) in Auctions::<T>::iter() {
+                ensure!(Kitties::<T>::contains_key(kitty_id), "auction of a missing kitty");
+                if let Some((bidder, amount)) = auction.and_then(|auction| auction.highest_bid) {
+                    hold(bidder, amount);
+                }
+            }
+            for (who, amount) in holds.iter() {
+                ensure!(T::Currency::reserved_balance_named(&Self::reserve_id(), who) >= *amount, "reserve below the stakes held");
+            }
+
+            for kitty_id in ListForSale::<T>::iter_keys() {
+                ensure!(Kitties::<T>::contains_key(kitty_id), "listing of a missing kitty");
+            }
+            for kitty_id in Leases::<T>::iter_keys() {
+                ensure!(Kitties::<T>::contains_key(kitty_id), "lease of a missing kitty");
+            }
+            for (bundle_id, bundle) in Bundles::<T>::iter() {
+                let bundle = bundle.ok_or("bundle entry without a bundle")?;
+                for kitty_id in bundle.kitties.iter() {
+                    ensure!(Self::owner(kitty_id) == Some(bundle.seller.clone()), "bundled kitty not owned by the seller");
+                    ensure!(Self::kitty_bundle(kitty_id) == Some(bundle_id), "bundled kitty not indexed");
+                }
+            }
+            Ok(())
+        }
+
+        // Reserve a stake from `who`, drawing it from the stake pool when the free balance can't cover it.
+        // A pooled balance is reserved already, so it only moves from the pool to the kitty.
+        fn reserve_stake(who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+            if T::Currency::reserve_named(&Self::reserve_id(), who, amount).is_ok() {
+                return Ok(());
+            }
+            let from_pool = Self::stake_pool(who).min(amount);
+            if from_pool.is_zero() {
+                return Err(Error::<T>::NotEnoughBalanceForStaking.into());
+            }
+            T::Currency::reserve_named(&Self::reserve_id(), who, amount.saturating_sub(from_pool))
+                .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+            StakePool::<T>::mutate(who, |pool| *pool = pool.saturating_sub(from_pool));
+            Self::deposit_event(Event::StakeToppedUp(who.clone(), from_pool));
+            Ok(())
+        }
+
+        // Ensure a kitty is not being auctioned, so it stays with its seller until the auction is settled.
+        fn ensure_not_auctioned(kitty_id: T::KittyIndex) -> DispatchResult {
+            ensure!(Self::auctions(kitty_id).is_none(), Error::<T>::KittyInAuction);
+            Ok(())
+        }
+
+        // Ensure the admin hasn't paused the market.
+        fn ensure_market_open() -> DispatchResult {
+            ensure!(!Self::is_market_paused(), Error::<T>::MarketPaused);
+            Ok(())
+        }
+
+        // Ensure a kitty isn't under a running lease, which keeps it with its owner.
+        fn ensure_not_leased(kitty_id: T::KittyIndex) -> DispatchResult {
+            ensure!(Self::active_tenant(kitty_id).is_none(), Error::<T>::KittyLeased);
+            Ok(())
+        }
+
+        // The tenant of a kitty's running lease, if any.
+        fn active_tenant(kitty_id: T::KittyIndex) -> Option<T::AccountId> {
+            Self::leases(kitty_id).filter(|lease| lease.end.is_some()).map(|lease| lease.tenant)
+        }
+
+        // End the leases running up to the start of block `n`. Returns the number ended.
+        fn expire_leases(n: T::BlockNumber) -> u32 {
+            let ending = LeasesEnding::<T>::take(n);
+            for kitty_id in ending.iter() {
+                if Leases::<T>::take(kitty_id).is_some() {
+                    Self::deposit_event(Event::LeaseEnded(*kitty_id));
+                }
+            }
+            ending.len() as u32
+        }
+
+        // The creator's royalty on a sale of a kitty for `price`, None when the creator is the seller.
+        fn royalty_for(kitty_id: T::KittyIndex, seller: &T::AccountId, price: BalanceOf<T>) -> Option<(T::AccountId, BalanceOf<T>)> {
+            let creator = Self::kitty_creator(kitty_id).filter(|creator| creator != seller)?;
+            let royalty = T::RoyaltyPercent::get() * price;
+            if royalty.is_zero() {
+                None
+            } else {
+                Some((creator, royalty))
+            }
+        }
+
+        // Lock a just sold kitty out of listing for PostBuyLock blocks.
+        fn lock_listing(kitty_id: T::KittyIndex) {
+            let until = <frame_system::Pallet<T>>::block_number().saturating_add(T::PostBuyLock::get());
+            ListingLockedUntil::<T>::insert(kitty_id, Some(until));
+        }
+
+        // Ensure a kitty is past the PostBuyLock of its last sale.
+        fn ensure_listing_unlocked(kitty_id: T::KittyIndex) -> DispatchResult {
+            if let Some(until) = Self::listing_locked_until(kitty_id) {
+                ensure!(<frame_system::Pallet<T>>::block_number() >= until, Error::<T>::PostBuyLockActive);
+            }
+            Ok(())
+        }
+
+        // Settle the auctions ending at block `n`. Returns the number settled.
+        fn settle_auctions(n: T::BlockNumber) -> u32 {
+            let ending = AuctionsEnding::<T>::take(n);
+            for kitty_id in ending.iter() {
+                let (seller, highest_bid) = match Auctions::<T>::take(kitty_id) {
+                    Some(auction) => (auction.seller, auction.highest_bid),
+                    None => continue,
+                };
+                let winner = highest_bid.and_then(|(bidder, amount)| {
+                    let result = with_transaction(|| {
+                        match Self::hand_over_reserved(*kitty_id, &seller, &bidder, amount) {
+                            Ok(()) => {
+                                Self::note_flip(&seller);
+                                TransactionOutcome::Commit(Ok(()))
+                            },
+                            Err(e) => TransactionOutcome::Rollback(Err(e)),
+                        }
+                    });
+                    match result {
+                        Ok(()) => Some((bidder, amount)),
+                        Err(_) => {
+                            // The kitty stays with the seller, and the bid is refunded.
+                            T::Currency::unreserve_named(&Self::reserve_id(), &bidder, amount);
+                            None
+                        },
+                    }
+                });
+                Self::deposit_event(Event::AuctionSettled(*kitty_id, winner));
+            }
+            ending.len() as u32
+        }
+
+        // Sell a kitty to the winner of its auction, an accepted offer or a bundle for the amount reserved
+        // from it, less the platform cut and the royalty, moving the stake like buy() does.
+        // The caller counts the seller's flip, once per sale.
+        fn hand_over_reserved(kitty_id: T::KittyIndex, seller: &T::AccountId, winner: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+            ensure!(Self::is_owner(seller, kitty_id), Error::<T>::NotOwner);
+            // A running lease keeps the kitty with its owner until it ends.
+            Self::ensure_not_leased(kitty_id)?;
+            Self::ensure_can_own(winner)?;
+            let mut seller_amount = amount;
+            if let Some((percent, receiver)) = Self::platform_fee() {
+                let fee = percent * amount;
+                T::Currency::repatriate_reserved_named(&Self::reserve_id(), winner, &receiver, fee, BalanceStatus::Free)?;
+                seller_amount = seller_amount.saturating_sub(fee);
+                Self::deposit_event(Event::PlatformFeePaid(receiver, kitty_id, fee));
+            }
+            if let Some((creator, royalty)) = Self::royalty_for(kitty_id, seller, amount) {
+                let royalty = royalty.min(seller_amount);
+                T::Currency::repatriate_reserved_named(&Self::reserve_id(), winner, &creator, royalty, BalanceStatus::Free)?;
+                seller_amount = seller_amount.saturating_sub(royalty);
+                Self::deposit_event(Event::RoyaltyPaid(creator, kitty_id, royalty));
+            }
+            T::Currency::repatriate_reserved_named(&Self::reserve_id(), winner, seller, seller_amount, BalanceStatus::Free)?;
+
+            let stake = Self::current_stake_for(winner);
+            T::Currency::reserve_named(&Self::reserve_id(), winner, stake)
+                .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+            Self::release_stake(seller, kitty_id, Self::stake_of(kitty_id))?;
+
+            Owner::<T>::insert(kitty_id, Some(winner.clone()));
+            ListForSale::<T>::remove(kitty_id);
+            ListingStakeBonus::<T>::remove(kitty_id);
+            ListingAssetPrice::<T>::remove(kitty_id);
+            ListingExpiry::<T>::remove(kitty_id);
+            Self::clear_owner_state(kitty_id);
+            Self::set_kitty_stake(kitty_id, stake);
+            Self::lock_listing(kitty_id);
+            Self::note_released(seller, kitty_id);
+            Self::note_acquired(winner, kitty_id)?;
+            Self::note_sale(kitty_id, amount);
+            Self::deposit_event(Event::KittySold(seller.clone(), winner.clone(), kitty_id, amount));
+            Self::deposit_event(Event::KittyTransferred(seller.clone(), winner.clone(), kitty_id));
+
+            Ok(())
+        }
+
+        // Ensure the per-block mint cap is not reached yet.
+        fn ensure_mint_allowed() -> DispatchResult {
+            ensure!(Self::mints_this_block() < T::MaxMintsPerBlock::get(), Error::<T>::MintRateLimited);
+            Ok(())
+        }
+
+        // Pay the stud fee for breeding with a parent owned by another account, which must be listed as a stud.
+        fn pay_stud_fee(who: &T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+            let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+            // The tenant of a running lease has paid the rent, and is the only one to breed with the kitty.
+            if let Some(tenant) = Self::active_tenant(kitty_id) {
+                ensure!(&tenant == who, Error::<T>::KittyLeased);
+                return Ok(());
+            }
+            if &owner == who {
+                return Ok(());
+            }
+            let fee = Self::stud_listings(kitty_id).ok_or(Error::<T>::NotStud)?;
+            T::Currency::transfer(who, &owner, fee, ExistenceRequirement::KeepAlive)?;
+            Self::deposit_event(Event::StudFeePaid(who.clone(), owner, kitty_id, fee));
+            Ok(())
+        }
+
+        // Ensure two kitties can be parents together: distinct existing kitties of opposite genders, neither a certificate.
+        fn ensure_breedable(kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) -> DispatchResult {
+            ensure!(kitty_id_1 != kitty_id_2, Error::<T>::SameParentIndex);
+            ensure!(!Self::is_certificate(kitty_id_1) && !Self::is_certificate(kitty_id_2), Error::<T>::KittyIsCertificate);
+            let kitty1 = Self::kitties(kitty_id_1).ok_or(Error::<T>::InvalidKittyIndex)?;
+            let kitty2 = Self::kitties(kitty_id_2).ok_or(Error::<T>::InvalidKittyIndex)?;
+            ensure!(kitty1.gender != kitty2.gender, Error::<T>::SameGenderParents);
+            Ok(())
+        }
+
+        // Breed a kitty for `who` from the parents, shared by breed() and breed_with_stud().
+        fn do_breed(who: &T::AccountId, kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) -> Result<T::KittyIndex, DispatchError> {
+            // Ensure the parents are not same.
+            ensure!(kitty_id_1 != kitty_id_2, Error::<T>::SameParentIndex);
+            // Certificates can't be parents.
+            ensure!(!Self::is_certificate(kitty_id_1) && !Self::is_certificate(kitty_id_2), Error::<T>::KittyIsCertificate);
+            // Ensure there're the parents in the Storage.
+            let kitty1 = Self::kitties(kitty_id_1).ok_or(Error::<T>::InvalidKittyIndex)?;
+            let kitty2 = Self::kitties(kitty_id_2).ok_or(Error::<T>::InvalidKittyIndex)?;
+            ensure!(kitty1.gender != kitty2.gender, Error::<T>::SameGenderParents);
+            ensure!(!Self::on_cooldown(kitty_id_1) && !Self::on_cooldown(kitty_id_2), Error::<T>::KittyOnCooldown);
+            // Breed new kitty from the parents.
+            let seed = Self::random_value(who);
+            let new_dna = T::GeneticsEngine::combine(&kitty1.dna, &kitty2.dna, &seed);
+            let kitty_id = Self::new_kitty_with_stake(who, new_dna)?;
+            Self::note_random_source(kitty_id);
+            let generation = Self::generation_of(kitty_id_1).max(Self::generation_of(kitty_id_2)).saturating_add(1);
+            KittyMetadata::<T>::mutate(kitty_id, |metadata| {
+                if let Some(metadata) = metadata {
+                    metadata.parents = Some((kitty_id_1, kitty_id_2));
+                    metadata.generation = generation;
+                }
+            });
+            let cooldown = T::BreedingCooldown::get();
+            if !cooldown.is_zero() {
+                let next_breed_at = <frame_system::Pallet<T>>::block_number().saturating_add(cooldown);
+                NextBreedAt::<T>::insert(kitty_id_1, Some(next_breed_at));
+                NextBreedAt::<T>::insert(kitty_id_2, Some(next_breed_at));
+            }
+            Self::deposit_event(Event::KittyBred(kitty_id_1, kitty_id_2, kitty_id));
+            Ok(kitty_id)
+        }
+
+        // Whether a kitty bred within the last BreedingCooldown blocks.
+        fn on_cooldown(kitty_id: T::KittyIndex) -> bool {
+            Self::next_breed_at(kitty_id).map_or(false, |at| <frame_system::Pallet<T>>::block_number() < at)
+        }
+
+        // The generation of a kitty, 0 for one without recorded provenance.
+        fn generation_of(kitty_id: T::KittyIndex) -> u32 {
+            Self::kitty_metadata(kitty_id).map_or(0, |metadata| metadata.generation)
+        }
+
+        // Helper function for optimizing the codes from create() and transfer().
+        fn new_kitty_with_stake(owner: &T::AccountId, dna: [u8; 16]) -> Result<T::KittyIndex, DispatchError> {
+            let stake = Self::current_stake_for(owner);
+            let kitty_id = Self::new_kitty(owner, dna, stake)?;
+            Self::set_kitty_stake(kitty_id, stake);
+            KittyCreator::<T>::insert(kitty_id, Some(owner.clone()));
+            Ok(kitty_id)
+        }
+
+        // Mint a kitty reserving the given stake from the owner.
+        fn new_kitty(owner: &T::AccountId, dna: [u8; 16], stake: BalanceOf<T>) -> Result<T::KittyIndex, DispatchError> {
+
+            let kitty_id = Self::next_kitty_id()?;
+            Self::ensure_mint_allowed()?;
+            Self::ensure_can_own(owner)?;
+
+            T::Currency::reserve_named(&Self::reserve_id(), &owner, stake)
+                .map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+
+            Self::insert_kitty(kitty_id, owner, dna)?;
+
+            Ok(kitty_id)
+        }
+
+        // Store a freshly minted kitty at the next index, once its stake is settled.
+        fn insert_kitty(kitty_id: T::KittyIndex, owner: &T::AccountId, dna: [u8; 16]) -> DispatchResult {
+            Self::note_acquired(owner, kitty_id)?;
+            Kitties::<T>::insert(kitty_id, Some(Kitty::new(dna)));
+            Self::index_traits(kitty_id, &dna);
+            Self::note_birth(kitty_id);
+            Owner::<T>::insert(kitty_id, Some(owner.clone()));
+            KittyExpiry::<T>::insert(kitty_id, Self::new_expiry());
+            KittiesCount::<T>::put(kitty_id + 1u32.into());
+            MintsThisBlock::<T>::mutate(|count| *count += 1);
+
+            Self::deposit_event(Event::KittyCreated(owner.clone(), kitty_id));
+
+            Ok(())
+        }
+
+   }
+
+    // Kitties exposed as a single class of non-fungible instances, for pallets that work with any nonfungibles.
+    impl<T: Config> nonfungibles::Inspect<T::AccountId> for Pallet<T> {
+        type InstanceId = T::KittyIndex;
+        type ClassId = ();
+
+        fn owner(_class: &(), instance: &T::KittyIndex) -> Option<T::AccountId> {
+            Owner::<T>::get(instance)
+        }
+
+        /// A kitty's `dna`, and its `name` once it is named.
+        fn attribute(_class: &(), instance: &T::KittyIndex, key: &[u8]) -> Option<Vec<u8>> {
+            match key {
+                b"dna" => Self::kitties(instance).map(|kitty| kitty.dna.to_vec()),
+                b"name" => Self::kitty_metadata_of(instance).map(|name| name.name.into_inner()),
+                _ => None,
+            }
+        }
+
+        fn can_transfer(_class: &(), instance: &T::KittyIndex) -> bool {
+            !Self::is_certificate(instance)
+                && Self::ensure_not_auctioned(*instance).is_ok()
+                && Self::ensure_not_leased(*instance).is_ok()
+        }
+    }
+
+    impl<T: Config> nonfungibles::Transfer<T::AccountId> for Pallet<T> {
+        fn transfer(_class: &(), instance: &T::KittyIndex, destination: &T::AccountId) -> DispatchResult {
+            let owner = Owner::<T>::get(instance).ok_or(Error::<T>::InvalidKittyIndex)?;
+            with_transaction(|| match Self::do_transfer(owner, destination.clone(), *instance) {
+                Ok(()) => TransactionOutcome::Commit(Ok(())),
+                Err(e) => TransactionOutcome::Rollback(Err(e)),
+            })
+        }
+    }
+
+    impl<T: Config> nonfungibles::Mutate<T::AccountId> for Pallet<T> {
+        /// Mint a kitty with random DNA, staked by `who`. Indices are handed out in order, so only
+        /// the next one can be minted.
+        fn mint_into(_class: &(), instance: &T::KittyIndex, who: &T::AccountId) -> DispatchResult {
+            ensure!(Self::next_kitty_id()? == *instance, Error::<T>::InvalidKittyIndex);
+            with_transaction(|| {
+                let dna = Self::random_value(who);
+                match Self::new_kitty_with_stake(who, dna) {
+                    Ok(kitty_id) => {
+                        Self::note_random_source(kitty_id);
+                        TransactionOutcome::Commit(Ok(()))
+                    },
+                    Err(e) => TransactionOutcome::Rollback(Err(e)),
+                }
+            })
+        }
+
+        /// Burn a kitty under the same rules as `burn`, releasing its stake.
+        fn burn_from(_class: &(), instance: &T::KittyIndex) -> DispatchResult {
+            let kitty_id = *instance;
+            ensure!(Self::reservations(kitty_id).is_none(), Error::<T>::InvalidKittyIndex);
+            let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+            ensure!(!Self::is_certificate(kitty_id), Error::<T>::KittyIsCertificate);
+            Self::ensure_not_auctioned(kitty_id)?;
+            Self::ensure_not_leased(kitty_id)?;
+
+            with_transaction(|| match Self::release_stake(&owner, kitty_id, Self::stake_of(kitty_id)) {
+                Ok(()) => {
+                    Self::remove_kitty(&owner, kitty_id);
+                    Self::deposit_event(Event::KittyBurned(owner.clone(), kitty_id));
+                    TransactionOutcome::Commit(Ok(()))
+                },
+                Err(e) => TransactionOutcome::Rollback(Err(e)),
+            })
+        }
+    }
 }
@@ -1,8 +1,8 @@
 use crate as pallet_kitties;
 use sp_core::H256;
-use frame_support::parameter_types;
+use frame_support::{parameter_types, traits::GenesisBuild, PalletId};
 use sp_runtime::{
-	traits::{BlakeTwo256, IdentityLookup}, testing::Header,
+	traits::{BlakeTwo256, IdentityLookup}, testing::Header, Percent,
 };
 use frame_system as system;
 
@@ -19,9 +19,10 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
-		KittiesModule: pallet_kitties::{Pallet, Call, Storage, Event<T>},
+		KittiesModule: pallet_kitties::{Pallet, Call, Storage, Event<T>, Config<T>},
         Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
         RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Pallet, Storage},
+        Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
 	}
 );
 
@@ -59,11 +60,12 @@ impl system::Config for Test {
 parameter_types! {
 	pub const ExistentialDeposit: u128 = 500;
 	pub const MaxLocks: u32 = 50;
+	pub const MaxReserves: u32 = 50;
 }
 
 impl pallet_balances::Config for Test {
 	type MaxLocks = MaxLocks;
-	type MaxReserves = ();
+	type MaxReserves = MaxReserves;
 	type ReserveIdentifier = [u8; 8];
 	type Balance = Balance;
 	type Event = Event;
@@ -75,8 +77,60 @@ impl pallet_balances::Config for Test {
 
 impl pallet_randomness_collective_flip::Config for Test {}
 
+parameter_types! {
+	pub const AssetDeposit: u128 = 1;
+	pub const ApprovalDeposit: u128 = 1;
+	pub const StringLimit: u32 = 50;
+	pub const MetadataDepositBase: u128 = 1;
+	pub const MetadataDepositPerByte: u128 = 1;
+}
+
+impl pallet_assets::Config for Test {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = u32;
+	type Currency = Balances;
+	type ForceOrigin = frame_system::EnsureRoot<u64>;
+	type AssetDeposit = AssetDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type ApprovalDeposit = ApprovalDeposit;
+	type StringLimit = StringLimit;
+	type Freezer = ();
+	type Extra = ();
+	type WeightInfo = ();
+}
+
 parameter_types! {
     pub const StakeForEachKitty: u128 = 10_000;
+    pub const KittiesPalletId: PalletId = PalletId(*b"py/kitty");
+    pub const ReferralReward: u128 = 1_000;
+    pub const MaxMintsPerBlock: u32 = 10;
+    pub const CertificateStake: u128 = 1_000;
+    pub const MaxCollectionNameLen: u32 = 32;
+    pub const ReversalWindow: u64 = 10;
+    pub const ExpiryBlocks: u64 = 100;
+    pub const RenewalFee: u128 = 1_000;
+    pub const MaxKittiesOwned: u32 = 20;
+    pub const NewOwnerRebate: u128 = 500;
+    pub const FlipWindow: u64 = 10;
+    pub const FlipThreshold: u32 = 3;
+    pub const FlipPenaltyFactor: u32 = 2;
+    pub const MaxQueuedBreeds: u32 = 20;
+    pub const MaxAuctionsEnding: u32 = 2;
+    pub const PostBuyLock: u64 = 5;
+    pub const RoyaltyPercent: Percent = Percent::from_percent(5);
+    // Disabled unless a test sets it.
+    pub static BreedingCooldown: u64 = 0;
+    pub const MaxBatchSize: u32 = 5;
+    pub const MaxKittyNameLen: u32 = 16;
+    pub const MaxKittyUriLen: u32 = 64;
+    pub const NameDepositPerByte: u128 = 10;
+    pub const MaxLeasesEnding: u32 = 2;
+    pub const MaxSalesRecorded: u32 = 2;
+    pub const RevealDelay: u64 = 3;
+    pub const MaxRevealsPerBlock: u32 = 2;
+    pub const SaleAsset: u32 = 0;
 }
 
 impl pallet_kitties::Config for Test {
@@ -85,6 +139,37 @@ impl pallet_kitties::Config for Test {
 	type KittyIndex = u32;
 	type StakeForEachKitty = StakeForEachKitty;
 	type Currency = Balances;
+	type PalletId = KittiesPalletId;
+	type ReferralReward = ReferralReward;
+	type MaxMintsPerBlock = MaxMintsPerBlock;
+	type CertificateStake = CertificateStake;
+	type MaxCollectionNameLen = MaxCollectionNameLen;
+	type AdminOrigin = frame_system::EnsureRoot<u64>;
+	type ReversalWindow = ReversalWindow;
+	type ExpiryBlocks = ExpiryBlocks;
+	type RenewalFee = RenewalFee;
+	type MaxKittiesOwned = MaxKittiesOwned;
+	type NewOwnerRebate = NewOwnerRebate;
+	type FlipWindow = FlipWindow;
+	type FlipThreshold = FlipThreshold;
+	type FlipPenaltyFactor = FlipPenaltyFactor;
+	type MaxQueuedBreeds = MaxQueuedBreeds;
+	type MaxAuctionsEnding = MaxAuctionsEnding;
+	type PostBuyLock = PostBuyLock;
+	type RoyaltyPercent = RoyaltyPercent;
+	type BreedingCooldown = BreedingCooldown;
+	type MaxBatchSize = MaxBatchSize;
+	type MaxKittyNameLen = MaxKittyNameLen;
+	type MaxKittyUriLen = MaxKittyUriLen;
+	type NameDepositPerByte = NameDepositPerByte;
+	type MaxLeasesEnding = MaxLeasesEnding;
+	type MaxSalesRecorded = MaxSalesRecorded;
+	type RevealDelay = RevealDelay;
+	type MaxRevealsPerBlock = MaxRevealsPerBlock;
+	type GeneticsEngine = pallet_kitties::genetics::BitmaskMixer;
+	type Assets = Assets;
+	type SaleAsset = SaleAsset;
+	type WeightInfo = ();
 }
 
 // Helper macro.
@@ -99,6 +184,11 @@ macro_rules! assert_has_event {
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
+	new_test_ext_with_kitties(vec![])
+}
+
+// Build genesis storage with the given kitties preloaded, as (owner, DNA).
+pub fn new_test_ext_with_kitties(kitties: Vec<(u64, [u8; 16])>) -> sp_io::TestExternalities {
 	let mut storage = system::GenesisConfig::default().build_storage::<Test>().unwrap();
 
 	// Genesis funds
@@ -108,6 +198,10 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 	.assimilate_storage(&mut storage)
 	.unwrap();
 
+	pallet_kitties::GenesisConfig::<Test> { kitties }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+
 	let mut ext = sp_io::TestExternalities::new(storage);
 	ext.execute_with(|| System::set_block_number(1));
 	ext
@@ -0,0 +1,36 @@
+//! Helpers for the mocks of runtimes using the kitties pallet: kitties minted with a chosen DNA
+//! rather than a random one, so tests and fuzzers get the same kitties on every run.
+//! Pair them with `Pallet::try_state()` to check the storage after each step.
+
+use sp_runtime::{DispatchError, traits::Zero};
+
+use crate::{Config, Gender, Pallet};
+
+/// A DNA of the given gender, with every other byte set to `seed`.
+pub fn dna_of(gender: Gender, seed: u8) -> [u8; 16] {
+	let mut dna = [seed; 16];
+	dna[15] = match gender {
+		Gender::Male => 0,
+		Gender::Female => 1,
+	};
+	dna
+}
+
+/// Mint a kitty with the given DNA for `owner`, which reserves the stake like for any mint.
+pub fn mint<T: Config>(owner: &T::AccountId, dna: [u8; 16]) -> Result<T::KittyIndex, DispatchError> {
+	let kitty_id = Pallet::<T>::kitties_count().unwrap_or_else(Zero::zero);
+	Pallet::<T>::reserve_index(frame_system::RawOrigin::Signed(owner.clone()).into())?;
+	Pallet::<T>::mint_reserved(frame_system::RawOrigin::Signed(owner.clone()).into(), kitty_id, dna)?;
+	Ok(kitty_id)
+}
+
+/// Mint `count` kitties for `owner`, alternating male and female, the i-th one seeded with i.
+/// They count towards MaxMintsPerBlock like any other mints.
+pub fn mint_many<T: Config>(owner: &T::AccountId, count: u8) -> Result<Vec<T::KittyIndex>, DispatchError> {
+	(0..count)
+		.map(|i| {
+			let gender = if i % 2 == 0 { Gender::Male } else { Gender::Female };
+			mint::<T>(owner, dna_of(gender, i))
+		})
+		.collect()
+}
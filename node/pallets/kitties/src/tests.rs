@@ -1,6 +1,19 @@
-use crate::mock::{Event as TestEvent, new_test_ext, KittiesModule, Origin, System, Test};
-use frame_support::{assert_ok, assert_noop};
+use crate::mock::{Event as TestEvent, new_test_ext, new_test_ext_with_kitties, Assets, Balances, BreedingCooldown, KittiesModule, Origin, RandomnessCollectiveFlip, System, Test};
+use frame_support::{assert_ok, assert_noop, traits::{Currency, NamedReservableCurrency, OnInitialize, OnRuntimeUpgrade, Randomness, ReservableCurrency}};
 use super::*;
+use sp_runtime::Percent;
+
+// Kitties minted in the same block can share DNA, so tests pick the genders of the parents.
+fn set_gender(kitty_id: u32, gender: Gender) {
+	Kitties::<Test>::mutate(kitty_id, |kitty| {
+		let mut dna = kitty.as_ref().unwrap().dna;
+		dna[15] = match gender {
+			Gender::Male => 0,
+			Gender::Female => 1,
+		};
+		*kitty = Some(Kitty::new(dna));
+	});
+}
 
 #[test]
 fn create_works() {
@@ -77,12 +90,17 @@ fn breed_works() {
 		assert_ok!(KittiesModule::create(Origin::signed(1)));
 		// Prepare kitty index=1, by AccountID =2.
 		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		// Kitty 1 belongs to AccountID =2, so it must be a stud.
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(2), 1, Some(0)));
 		// Breed a kitty index=2 from 0&1, by AccountID =1.
 		assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
 		assert_eq!(KittiesCount::<Test>::get(), Some(3));
 		// Test the Event emitted already.
 		// Event::<Test>::KittyCreated(Owner, KittyIndex)
 		assert_has_event!(Event::<Test>::KittyCreated(1, 2));
+		assert_has_event!(Event::<Test>::KittyBred(0, 1, 2));
 	});
 }
 
@@ -117,6 +135,10 @@ fn breed_failed_when_not_enough_balance_for_staking() {
 		assert_ok!(KittiesModule::create(Origin::signed(1)));
 		// Prepare kitty index=1, by AccountID =2.
 		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(1), 0, Some(0)));
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(2), 1, Some(0)));
 		// Account 3 has not enough balance for staing
 		assert_noop!(KittiesModule::breed(Origin::signed(3), 0, 1), Error::<Test>::NotEnoughBalanceForStaking);
 	});
@@ -129,11 +151,15 @@ fn sell_works() {
 		assert_ok!(KittiesModule::create(Origin::signed(1)));
 		// List Kitty index=0 for sale with a price=1_500.
 		let price: u128 = 1_500;
-		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(price)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(price), 0, None));
 		assert_eq!(ListForSale::<Test>::get(0), Some(price));
 		// Test the Event emitted already.
 		// KittyListed(T::AccountId, T::KittyIndex, Option<BalanceOf<T>>)
 		assert_has_event!(Event::<Test>::KittyListed(1, 0, Some(price)));
+		// Removing the listing emits KittyDelisted instead.
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, None, 0, None));
+		assert_eq!(ListForSale::<Test>::get(0), None);
+		assert_has_event!(Event::<Test>::KittyDelisted(1, 0));
 	});
 }
 
@@ -144,7 +170,7 @@ fn sell_failed_when_not_owner() {
 		assert_ok!(KittiesModule::create(Origin::signed(1)));
 		// List Kitty index=0 for sale with a price=1_500, but not by the owner.
 		let price: u128 = 1_500;
-		assert_noop!(KittiesModule::sell(Origin::signed(3), 0, Some(price)), Error::<Test>::NotOwner);
+		assert_noop!(KittiesModule::sell(Origin::signed(3), 0, Some(price), 0, None), Error::<Test>::NotOwner);
 	});
 }
 
@@ -155,13 +181,22 @@ fn buy_works() {
 		assert_ok!(KittiesModule::create(Origin::signed(1)));
 		// List Kitty index=0 for sale with a price=1_500.
 		let price: u128 = 1_500;
-		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(price)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(price), 0, None));
 		// AccountID=2 buy KittyIndex=0 (from AccountID=1)
 		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
 		assert_eq!(Owner::<Test>::get(0), Some(2));
 		// Test the Event emitted.
 		// KittyTransferred(Seller, Buyer, KittyIndex)
 		assert_has_event!(Event::<Test>::KittyTransferred(1, 2, 0));
+		assert_has_event!(Event::<Test>::KittySold(1, 2, 0, price));
+	});
+}
+
+#[test]
+fn buy_failed_when_invalid_kitty_index() {
+	new_test_ext().execute_with(|| {
+		// No KittyIndex=0 minted yet.
+		assert_noop!(KittiesModule::buy(Origin::signed(2), 0), Error::<Test>::InvalidKittyIndex);
 	});
 }
 
@@ -172,7 +207,7 @@ fn buy_failed_when_buyer_is_owner() {
 		assert_ok!(KittiesModule::create(Origin::signed(1)));
 		// List Kitty index=0 for sale with a price=1_500.
 		let price: u128 = 1_500;
-		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(price)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(price), 0, None));
 		// AccountID=1 (is owner) buy KittyIndex=0 (from AccountID=1)
 		assert_noop!(KittiesModule::buy(Origin::signed(1), 0), Error::<Test>::BuyerIsOwner);
 	});
@@ -184,20 +219,2331 @@ fn buy_failed_when_not_for_sale() {
 		// Prepare kitty index=0, by AccountID =1.
 		assert_ok!(KittiesModule::create(Origin::signed(1)));
 		// List Kitty index=0 for sale with a price=None, which means not for sale.
-		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, None));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, None, 0, None));
 		// AccountID=2 buy KittyIndex=0 (from AccountID=1), but the kitty is not for sale.
 		assert_noop!(KittiesModule::buy(Origin::signed(2), 0), Error::<Test>::NotForSale);
 	});
 }
 
+#[test]
+fn buy_failed_when_listed_kitty_was_transferred() {
+	new_test_ext().execute_with(|| {
+		// Prepare kitty index=0, by AccountID =1, and list it for sale.
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
+		// Transferring the kitty drops the listing.
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert_eq!(ListForSale::<Test>::get(0), None);
+		// AccountID=1 can't buy it back at the old price.
+		assert_noop!(KittiesModule::buy(Origin::signed(1), 0), Error::<Test>::NotForSale);
+	});
+}
+
 #[test]
 fn buy_failed_when_not_enough_balance() {
 	new_test_ext().execute_with(|| {
 		// Prepare kitty index=0, by AccountID =1.
 		assert_ok!(KittiesModule::create(Origin::signed(1)));
 		// List Kitty index=0 for sale with a price=1_500.
-		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
 		// AccountID=3 (who is poor) buy KittyIndex=0 (from AccountID=1).
 		assert_noop!(KittiesModule::buy(Origin::signed(3), 0), Error::<Test>::NotEnoughBalanceForBuying);
 	});
 }
+
+#[test]
+fn reserve_index_then_mint_works() {
+	new_test_ext().execute_with(|| {
+		// AccountID=1 reserves KittyIndex=0.
+		assert_ok!(KittiesModule::reserve_index(Origin::signed(1)));
+		assert_eq!(KittiesCount::<Test>::get(), Some(1));
+		assert_eq!(Reservations::<Test>::get(0), Some(1));
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert_has_event!(Event::<Test>::KittyIndexReserved(1, 0));
+		// A normal mint skips the reserved index.
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		assert_eq!(Owner::<Test>::get(1), Some(2));
+		// Fill the reservation.
+		assert_ok!(KittiesModule::mint_reserved(Origin::signed(1), 0, [7u8; 16]));
+		assert_eq!(Owner::<Test>::get(0), Some(1));
+		assert_eq!(Kitties::<Test>::get(0).map(|k| k.dna), Some([7u8; 16]));
+		assert_eq!(Reservations::<Test>::get(0), None);
+		assert_eq!(KittyCreator::<Test>::get(0), Some(1));
+		// The stake was taken at reservation time only.
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert_has_event!(Event::<Test>::KittyCreated(1, 0));
+	});
+}
+
+#[test]
+fn mint_reserved_failed_when_not_reservation_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::reserve_index(Origin::signed(1)));
+		// AccountID=2 tries to fill the reservation of AccountID=1.
+		assert_noop!(KittiesModule::mint_reserved(Origin::signed(2), 0, [7u8; 16]), Error::<Test>::NotReservationOwner);
+		// No reservation for KittyIndex=1.
+		assert_noop!(KittiesModule::mint_reserved(Origin::signed(1), 1, [7u8; 16]), Error::<Test>::InvalidKittyIndex);
+	});
+}
+
+#[test]
+fn dna_exists_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		let dna = Kitties::<Test>::get(0).unwrap().dna;
+		assert!(KittiesModule::dna_exists(dna));
+		// A novel DNA is not found.
+		let mut novel = dna;
+		novel[0] = !novel[0];
+		assert!(!KittiesModule::dna_exists(novel));
+	});
+}
+
+#[test]
+fn create_referred_works() {
+	new_test_ext().execute_with(|| {
+		// Fund the referral pool.
+		let pool = KittiesModule::account_id();
+		Balances::make_free_balance_be(&pool, 1_000_000);
+		// AccountID=2 mints a kitty referred by AccountID=3.
+		assert_ok!(KittiesModule::create_referred(Origin::signed(2), 3));
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		assert_eq!(Balances::free_balance(3), 9_000 + 1_000);
+		assert_eq!(ReferralCount::<Test>::get(3), 1);
+		assert_has_event!(Event::<Test>::ReferralRewarded(3, 2, 1_000));
+	});
+}
+
+#[test]
+fn create_referred_failed_when_self_referral() {
+	new_test_ext().execute_with(|| {
+		let pool = KittiesModule::account_id();
+		Balances::make_free_balance_be(&pool, 1_000_000);
+		assert_noop!(KittiesModule::create_referred(Origin::signed(2), 2), Error::<Test>::SelfReferral);
+	});
+}
+
+#[test]
+fn create_referred_failed_when_pool_exhausted() {
+	new_test_ext().execute_with(|| {
+		// The pool is empty, so nothing is minted.
+		assert_noop!(KittiesModule::create_referred(Origin::signed(2), 3), Error::<Test>::ReferralPoolExhausted);
+	});
+}
+
+#[test]
+fn create_failed_when_mint_rate_limited() {
+	new_test_ext().execute_with(|| {
+		// Mint up to MaxMintsPerBlock=10 kitties in block 1.
+		for _ in 0..10 {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+		}
+		assert_noop!(KittiesModule::create(Origin::signed(1)), Error::<Test>::MintRateLimited);
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_noop!(KittiesModule::breed(Origin::signed(1), 0, 1), Error::<Test>::MintRateLimited);
+		// The counter resets in the next block.
+		System::set_block_number(2);
+		KittiesModule::on_initialize(2);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_eq!(MintsThisBlock::<Test>::get(), 1);
+	});
+}
+
+#[test]
+fn buy_with_stake_bonus_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		// List with a price=1_500, leaving 4_000 of the 10_000 stake to the buyer.
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 4_000, None));
+		assert_eq!(ListingStakeBonus::<Test>::get(0), 4_000);
+		let buyer_free = Balances::free_balance(2);
+		let seller_free = Balances::free_balance(1);
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		// The buyer reserved only 6_000 itself, but holds the full stake.
+		assert_eq!(Balances::free_balance(2), buyer_free - 1_500 - 6_000);
+		assert_eq!(Balances::reserved_balance(2), 10_000);
+		// The seller got back 6_000 of the stake plus the price.
+		assert_eq!(Balances::free_balance(1), seller_free + 1_500 + 6_000);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(ListingStakeBonus::<Test>::get(0), 0);
+	});
+}
+
+#[test]
+fn sell_failed_when_stake_bonus_too_high() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 10_001, None), Error::<Test>::StakeBonusTooHigh);
+	});
+}
+
+#[test]
+fn mint_certificate_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::mint_certificate(Origin::signed(1), [9u8; 16]));
+		assert_eq!(Owner::<Test>::get(0), Some(1));
+		assert!(Certificates::<Test>::get(0));
+		// Only the reduced stake is reserved.
+		assert_eq!(Balances::reserved_balance(1), 1_000);
+		assert_has_event!(Event::<Test>::KittyCreated(1, 0));
+	});
+}
+
+#[test]
+fn certificate_can_not_be_transferred_sold_or_bred() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::mint_certificate(Origin::signed(1), [9u8; 16]));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::transfer(Origin::signed(1), 2, 0), Error::<Test>::KittyIsCertificate);
+		assert_noop!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None), Error::<Test>::KittyIsCertificate);
+		assert_noop!(KittiesModule::breed(Origin::signed(1), 0, 1), Error::<Test>::KittyIsCertificate);
+		assert_noop!(KittiesModule::breed(Origin::signed(1), 1, 0), Error::<Test>::KittyIsCertificate);
+	});
+}
+
+#[test]
+fn destroy_certificate_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::mint_certificate(Origin::signed(1), [9u8; 16]));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		// Only the owner can destroy it, and only certificates can be destroyed.
+		assert_noop!(KittiesModule::destroy_certificate(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_noop!(KittiesModule::destroy_certificate(Origin::signed(1), 1), Error::<Test>::NotCertificate);
+		assert_ok!(KittiesModule::destroy_certificate(Origin::signed(1), 0));
+		assert_eq!(Owner::<Test>::get(0), None);
+		assert!(Kitties::<Test>::get(0).is_none());
+		assert!(!Certificates::<Test>::get(0));
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert_has_event!(Event::<Test>::CertificateDestroyed(1, 0));
+	});
+}
+
+#[test]
+fn breed_with_stud_works() {
+	new_test_ext().execute_with(|| {
+		// Kitty index=0 belongs to AccountID=1, stud index=1 to AccountID=2.
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_ok!(KittiesModule::list_stud(Origin::signed(2), 1, 2_000));
+		assert_has_event!(Event::<Test>::StudListed(2, 1, Some(2_000)));
+		let stud_owner_free = Balances::free_balance(2);
+		assert_ok!(KittiesModule::breed_with_stud(Origin::signed(1), 0, 1));
+		// The fee flows to the stud owner, and the offspring to the caller.
+		assert_eq!(Balances::free_balance(2), stud_owner_free + 2_000);
+		assert_eq!(Owner::<Test>::get(2), Some(1));
+		assert_has_event!(Event::<Test>::StudFeePaid(1, 2, 1, 2_000));
+		assert_has_event!(Event::<Test>::KittyCreated(1, 2));
+	});
+}
+
+#[test]
+fn breed_with_stud_failed_when_not_listed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		assert_noop!(KittiesModule::breed_with_stud(Origin::signed(1), 0, 1), Error::<Test>::NotStud);
+		// Unlisting removes the stud.
+		assert_ok!(KittiesModule::list_stud(Origin::signed(2), 1, 2_000));
+		assert_noop!(KittiesModule::unlist_stud(Origin::signed(1), 1), Error::<Test>::NotOwner);
+		assert_ok!(KittiesModule::unlist_stud(Origin::signed(2), 1));
+		assert_has_event!(Event::<Test>::StudListed(2, 1, None));
+		assert_noop!(KittiesModule::breed_with_stud(Origin::signed(1), 0, 1), Error::<Test>::NotStud);
+	});
+}
+
+#[test]
+fn is_owner_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert!(KittiesModule::is_owner(&1, 0));
+		assert!(!KittiesModule::is_owner(&2, 0));
+		// Nonexistent kitty.
+		assert!(!KittiesModule::is_owner(&1, 1));
+	});
+}
+
+#[test]
+fn dnas_of_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::mint_certificate(Origin::signed(1), [9u8; 16]));
+		let dna = Kitties::<Test>::get(0).unwrap().dna;
+		// A mix of existing and missing ids.
+		assert_eq!(KittiesModule::dnas_of(vec![1, 5, 0]), vec![Some([9u8; 16]), None, Some(dna)]);
+		// The query is bounded.
+		assert_eq!(KittiesModule::dnas_of(vec![0; 1_000]).len(), 256);
+	});
+}
+
+#[test]
+fn collection_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create_collection(Origin::signed(1), b"starters".to_vec()));
+		let collection = Collections::<Test>::get(0).unwrap();
+		assert_eq!(collection.owner, 1);
+		assert_eq!(collection.name.to_vec(), b"starters".to_vec());
+		assert_has_event!(Event::<Test>::CollectionCreated(1, 0));
+		// Add and remove a kitty.
+		assert_ok!(KittiesModule::add_to_collection(Origin::signed(1), 0, 0));
+		assert_eq!(KittyCollection::<Test>::get(0), Some(0));
+		assert_noop!(KittiesModule::add_to_collection(Origin::signed(1), 0, 0), Error::<Test>::AlreadyInCollection);
+		assert_ok!(KittiesModule::remove_from_collection(Origin::signed(1), 0, 0));
+		assert_eq!(KittyCollection::<Test>::get(0), None);
+		assert_noop!(KittiesModule::remove_from_collection(Origin::signed(1), 0, 0), Error::<Test>::NotInCollection);
+	});
+}
+
+#[test]
+fn collection_failed_when_not_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		assert_ok!(KittiesModule::create_collection(Origin::signed(1), b"starters".to_vec()));
+		// Neither someone else's collection nor someone else's kitty.
+		assert_noop!(KittiesModule::add_to_collection(Origin::signed(2), 0, 1), Error::<Test>::NotCollectionOwner);
+		assert_noop!(KittiesModule::add_to_collection(Origin::signed(1), 0, 1), Error::<Test>::NotOwner);
+		assert_noop!(KittiesModule::add_to_collection(Origin::signed(1), 1, 0), Error::<Test>::InvalidCollection);
+		assert_noop!(KittiesModule::create_collection(Origin::signed(1), vec![0u8; 33]), Error::<Test>::CollectionNameTooLong);
+	});
+}
+
+#[test]
+fn collection_membership_cleared_on_transfer() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create_collection(Origin::signed(1), b"starters".to_vec()));
+		assert_ok!(KittiesModule::add_to_collection(Origin::signed(1), 0, 0));
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert_eq!(KittyCollection::<Test>::get(0), None);
+	});
+}
+
+#[test]
+fn distinct_owners_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_eq!(DistinctOwners::<Test>::get(), 1);
+		assert_eq!(OwnedKitties::<Test>::get(1).len(), 2);
+		// AccountID=2 gains its first kitty.
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert_eq!(DistinctOwners::<Test>::get(), 2);
+		// AccountID=1 loses its last kitty.
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 1, Some(1_500), 0, None));
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 1));
+		assert_eq!(DistinctOwners::<Test>::get(), 1);
+		assert_eq!(OwnedKitties::<Test>::get(1).len(), 0);
+		assert_eq!(OwnedKitties::<Test>::get(2).len(), 2);
+	});
+}
+
+#[test]
+fn trade_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		// AccountID=1 offers kitty 0 plus 2_000 for kitty 1 of AccountID=2.
+		assert_ok!(KittiesModule::trade(Origin::signed(1), 0, 1, 2, 2_000));
+		assert_has_event!(Event::<Test>::TradeProposed(1, 2, 0, 1, 2_000));
+		let proposer_free = Balances::free_balance(1);
+		let counterparty_free = Balances::free_balance(2);
+		// Only the counterparty can accept.
+		assert_noop!(KittiesModule::accept_trade(Origin::signed(3), 0), Error::<Test>::NotTradeParty);
+		assert_ok!(KittiesModule::accept_trade(Origin::signed(2), 0));
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		assert_eq!(Owner::<Test>::get(1), Some(1));
+		// The payment flows from the proposer to the counterparty.
+		assert_eq!(Balances::free_balance(1), proposer_free - 2_000);
+		assert_eq!(Balances::free_balance(2), counterparty_free + 2_000);
+		assert!(Trades::<Test>::get(0).is_none());
+		assert_has_event!(Event::<Test>::TradeAccepted(0));
+	});
+}
+
+#[test]
+fn reject_trade_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		assert_noop!(KittiesModule::trade(Origin::signed(1), 0, 1, 1, 0), Error::<Test>::TradeWithSelf);
+		assert_noop!(KittiesModule::trade(Origin::signed(1), 0, 1, 3, 0), Error::<Test>::NotOwner);
+		assert_ok!(KittiesModule::trade(Origin::signed(1), 0, 1, 2, 0));
+		assert_ok!(KittiesModule::reject_trade(Origin::signed(2), 0));
+		assert_has_event!(Event::<Test>::TradeRejected(0));
+		// Nothing moved, and the trade can't be accepted anymore.
+		assert_noop!(KittiesModule::accept_trade(Origin::signed(2), 0), Error::<Test>::NoSuchTrade);
+		assert_eq!(Owner::<Test>::get(0), Some(1));
+		assert_eq!(Owner::<Test>::get(1), Some(2));
+	});
+}
+
+#[test]
+fn stake_payout_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::set_stake_payout(Origin::signed(2), 0, Some(3)), Error::<Test>::NotOwner);
+		assert_ok!(KittiesModule::set_stake_payout(Origin::signed(1), 0, Some(3)));
+		assert_has_event!(Event::<Test>::StakePayoutSet(1, 0, Some(3)));
+		let owner_free = Balances::free_balance(1);
+		// The released stake goes to AccountID=3 instead of the owner.
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), owner_free);
+		assert_eq!(Balances::free_balance(3), 9_000 + 10_000);
+		// The payout account doesn't carry over to the new owner.
+		assert_eq!(StakePayout::<Test>::get(0), None);
+	});
+}
+
+#[test]
+fn stake_payout_defaults_to_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		let owner_free = Balances::free_balance(1);
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert_eq!(Balances::free_balance(1), owner_free + 10_000);
+	});
+}
+
+#[test]
+fn buyable_by_works() {
+	new_test_ext().execute_with(|| {
+		// Kitty 0 and 1 of AccountID=1 are listed, kitty 2 of AccountID=2 too, kitty 3 is not.
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 1, Some(20_000_000_000), 0, None));
+		assert_ok!(KittiesModule::sell(Origin::signed(2), 2, Some(1_500), 0, None));
+		// AccountID=2 can't afford kitty 1 and already owns kitty 2.
+		assert_eq!(KittiesModule::buyable_by(&2, 10), vec![0]);
+		// AccountID=3 can't afford any stake.
+		assert_eq!(KittiesModule::buyable_by(&3, 10), Vec::<u32>::new());
+		assert_eq!(KittiesModule::buyable_by(&2, 0), Vec::<u32>::new());
+	});
+}
+
+#[test]
+fn reverse_transfer_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert_eq!(LastTransfer::<Test>::get(0), Some((1, 1)));
+		// Only the admin can reverse it.
+		assert_noop!(KittiesModule::reverse_transfer(Origin::signed(1), 0), sp_runtime::DispatchError::BadOrigin);
+		// Still within ReversalWindow=10 blocks.
+		System::set_block_number(11);
+		assert_ok!(KittiesModule::reverse_transfer(Origin::root(), 0));
+		assert_eq!(Owner::<Test>::get(0), Some(1));
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_has_event!(Event::<Test>::TransferReversed(2, 1, 0));
+		// A reversal can't be reversed.
+		assert_noop!(KittiesModule::reverse_transfer(Origin::root(), 0), Error::<Test>::NoTransferToReverse);
+	});
+}
+
+#[test]
+fn reverse_transfer_failed_when_window_passed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		System::set_block_number(12);
+		assert_noop!(KittiesModule::reverse_transfer(Origin::root(), 0), Error::<Test>::ReversalWindowPassed);
+	});
+}
+
+#[test]
+fn reroll_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		let old_dna = Kitties::<Test>::get(0).unwrap().dna;
+		assert_noop!(KittiesModule::reroll(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_ok!(KittiesModule::reroll(Origin::signed(1), 0));
+		// The old kitty is gone and a new one with different DNA exists.
+		assert!(Kitties::<Test>::get(0).is_none());
+		assert_eq!(Owner::<Test>::get(0), None);
+		assert_eq!(Owner::<Test>::get(1), Some(1));
+		assert_ne!(Kitties::<Test>::get(1).unwrap().dna, old_dna);
+		// The stake is carried over.
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert_has_event!(Event::<Test>::KittyBurned(1, 0));
+		assert_has_event!(Event::<Test>::KittyCreated(1, 1));
+	});
+}
+
+#[test]
+fn buy_with_platform_fee_works() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(KittiesModule::set_platform_fee(Origin::signed(1), Percent::from_percent(10), 3), sp_runtime::DispatchError::BadOrigin);
+		assert_ok!(KittiesModule::set_platform_fee(Origin::root(), Percent::from_percent(10), 3));
+		assert_has_event!(Event::<Test>::PlatformFeeSet(Percent::from_percent(10), 3));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(2_000), 0, None));
+		let seller_free = Balances::free_balance(1);
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		// 10% of the price goes to the platform receiver, the rest to the seller.
+		assert_eq!(Balances::free_balance(3), 9_000 + 200);
+		assert_eq!(Balances::free_balance(1), seller_free + 10_000 + 1_800);
+		assert_has_event!(Event::<Test>::PlatformFeePaid(3, 0, 200));
+	});
+}
+
+#[test]
+fn current_stake_for_matches_reserved_stake() {
+	new_test_ext().execute_with(|| {
+		let stake = KittiesModule::current_stake_for(&1);
+		assert_eq!(stake, 10_000);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_eq!(Balances::reserved_balance(1), stake);
+	});
+}
+
+#[test]
+fn renew_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		// Minted in block 1 with ExpiryBlocks=100.
+		assert_eq!(KittyExpiry::<Test>::get(0), Some(101));
+		assert_noop!(KittiesModule::renew(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_ok!(KittiesModule::renew(Origin::signed(1), 0));
+		assert_eq!(KittyExpiry::<Test>::get(0), Some(201));
+		assert_eq!(Balances::free_balance(KittiesModule::account_id()), 1_000);
+		assert_has_event!(Event::<Test>::KittyRenewed(1, 0, 201));
+		// Not expired yet.
+		System::set_block_number(201);
+		assert_noop!(KittiesModule::reclaim_expired(Origin::signed(2), 0), Error::<Test>::NotExpired);
+	});
+}
+
+#[test]
+fn reclaim_expired_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		System::set_block_number(102);
+		assert_noop!(KittiesModule::reclaim_expired(Origin::signed(1), 0), Error::<Test>::BuyerIsOwner);
+		assert_ok!(KittiesModule::reclaim_expired(Origin::signed(2), 0));
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		// The stakes are swapped, and the expiry restarts.
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(2), 10_000);
+		assert_eq!(KittyExpiry::<Test>::get(0), Some(202));
+		assert_has_event!(Event::<Test>::KittyReclaimed(2, 1, 0));
+	});
+}
+
+#[test]
+fn kitty_flags_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::mint_certificate(Origin::signed(1), [9u8; 16]));
+		assert_eq!(KittiesModule::kitty_flags(0), KittyFlags::default());
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
+		assert_eq!(KittiesModule::kitty_flags(0), KittyFlags { listed: true, ..Default::default() });
+		assert_eq!(KittiesModule::kitty_flags(1), KittyFlags { soulbound: true, ..Default::default() });
+		// A just bought kitty is locked out of listing.
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		assert_eq!(KittiesModule::kitty_flags(0), KittyFlags { locked: true, ..Default::default() });
+		// Bred parents are on cooldown.
+		BreedingCooldown::set(5);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		set_gender(2, Gender::Male);
+		set_gender(3, Gender::Female);
+		assert_ok!(KittiesModule::breed(Origin::signed(1), 2, 3));
+		assert_eq!(KittiesModule::kitty_flags(2), KittyFlags { on_cooldown: true, ..Default::default() });
+		assert_ok!(KittiesModule::start_auction(Origin::signed(1), 4, 1_000, 5));
+		assert_eq!(KittiesModule::kitty_flags(4), KittyFlags { on_auction: true, ..Default::default() });
+	});
+}
+
+#[test]
+fn owned_kitties_works() {
+	new_test_ext().execute_with(|| {
+		// Kitty 0 and 2 belong to AccountID=1, kitty 1 to AccountID=2.
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_eq!(OwnedKitties::<Test>::get(1).to_vec(), vec![0, 2]);
+		assert_eq!(OwnedKitties::<Test>::get(2).to_vec(), vec![1]);
+		// Transfer kitty 0 to AccountID=2.
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert_eq!(OwnedKitties::<Test>::get(1).to_vec(), vec![2]);
+		assert_eq!(OwnedKitties::<Test>::get(2).to_vec(), vec![1, 0]);
+		// Buy kitty 2 by AccountID=2.
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 2, Some(1_500), 0, None));
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 2));
+		assert!(OwnedKitties::<Test>::get(1).is_empty());
+		assert_eq!(OwnedKitties::<Test>::get(2).to_vec(), vec![1, 0, 2]);
+	});
+}
+
+#[test]
+fn transfer_to_new_owner_with_rebate_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		// AccountID=2 has no kitties, so it reserves StakeForEachKitty - NewOwnerRebate.
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert_eq!(Balances::reserved_balance(2), 9_500);
+		assert_eq!(KittyStake::<Test>::get(0), Some(9_500));
+		// The second kitty needs the full stake.
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 1));
+		assert_eq!(Balances::reserved_balance(2), 9_500 + 10_000);
+		assert_eq!(KittyStake::<Test>::get(1), None);
+		// Only the reduced stake is released when the kitty moves on.
+		assert_ok!(KittiesModule::transfer(Origin::signed(2), 1, 0));
+		assert_eq!(Balances::reserved_balance(2), 10_000);
+	});
+}
+
+#[test]
+fn buy_works_when_balance_exactly_enough() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
+		// Price + stake + existential deposit.
+		Balances::make_free_balance_be(&4, 1_500 + 10_000 + 500);
+		assert_ok!(KittiesModule::buy(Origin::signed(4), 0));
+		assert_eq!(Owner::<Test>::get(0), Some(4));
+		assert_eq!(Balances::free_balance(4), 500);
+	});
+}
+
+#[test]
+fn buy_failed_when_balance_one_below_enough() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
+		Balances::make_free_balance_be(&4, 1_500 + 10_000 + 500 - 1);
+		assert_noop!(KittiesModule::buy(Origin::signed(4), 0), Error::<Test>::NotEnoughBalanceForBuying);
+	});
+}
+
+#[test]
+fn breed_all_compatible_works() {
+	new_test_ext().execute_with(|| {
+		// Three kitties give two pairs of opposite genders.
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		set_gender(2, Gender::Male);
+		assert_ok!(KittiesModule::breed_all_compatible(Origin::signed(1), 10));
+		assert_eq!(KittiesCount::<Test>::get(), Some(5));
+		assert_eq!(Owner::<Test>::get(4), Some(1));
+		assert_has_event!(Event::<Test>::BredAllCompatible(1, 2));
+		// Two kitties give a single pair, bred at most once per call.
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		set_gender(5, Gender::Male);
+		set_gender(6, Gender::Female);
+		assert_ok!(KittiesModule::breed_all_compatible(Origin::signed(2), 10));
+		assert_eq!(KittiesCount::<Test>::get(), Some(8));
+		assert_has_event!(Event::<Test>::BredAllCompatible(2, 1));
+	});
+}
+
+#[test]
+fn breed_all_compatible_stops_when_funds_run_out() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		// AccountID=4 affords the stakes of two kitties, but not of any offspring.
+		Balances::make_free_balance_be(&4, 9_500 + 10_000 + 5_000);
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 4, 0));
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 4, 1));
+		assert_ok!(KittiesModule::breed_all_compatible(Origin::signed(4), 10));
+		assert_eq!(KittiesCount::<Test>::get(), Some(2));
+		assert_has_event!(Event::<Test>::BredAllCompatible(4, 0));
+	});
+}
+
+#[test]
+fn burn_listed_kitty_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert_ok!(KittiesModule::burn(Origin::signed(1), 0));
+		// The stake is released and the kitty is gone, listing included.
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Kitties::<Test>::get(0), None);
+		assert_eq!(Owner::<Test>::get(0), None);
+		assert_eq!(ListForSale::<Test>::get(0), None);
+		assert_has_event!(Event::<Test>::KittyBurned(1, 0));
+		// The index stays taken.
+		assert_eq!(KittiesCount::<Test>::get(), Some(1));
+		assert_noop!(KittiesModule::buy(Origin::signed(2), 0), Error::<Test>::NotForSale);
+		assert_noop!(KittiesModule::burn(Origin::signed(1), 0), Error::<Test>::KittyAlreadyBurned);
+	});
+}
+
+#[test]
+fn burn_failed_when_not_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::burn(Origin::signed(2), 0), Error::<Test>::NotOwner);
+	});
+}
+
+#[test]
+fn burn_failed_when_invalid_kitty_index() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(KittiesModule::burn(Origin::signed(1), 0), Error::<Test>::InvalidKittyIndex);
+		// A reserved index is not a kitty yet.
+		assert_ok!(KittiesModule::reserve_index(Origin::signed(1)));
+		assert_noop!(KittiesModule::burn(Origin::signed(1), 0), Error::<Test>::InvalidKittyIndex);
+	});
+}
+
+#[test]
+fn kitty_random_source_is_recorded_at_mint() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(5);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+		let (seed, _) = RandomnessCollectiveFlip::random_seed();
+		let mut reference = [0u8; 8];
+		reference.copy_from_slice(&seed.as_ref()[..8]);
+		assert_eq!(KittiesModule::kitty_random_source(0), Some((5, reference)));
+		assert_eq!(KittiesModule::kitty_random_source(2), Some((5, reference)));
+		// A certificate's DNA is chosen by its owner, not drawn.
+		assert_ok!(KittiesModule::mint_certificate(Origin::signed(1), [1u8; 16]));
+		assert_eq!(KittiesModule::kitty_random_source(3), None);
+	});
+}
+
+#[test]
+fn stake_escalated_after_rapid_transfers() {
+	new_test_ext().execute_with(|| {
+		for kitty_id in 0..3 {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, kitty_id));
+		}
+		assert_eq!(KittiesModule::recent_flips(&1), 3);
+		// AccountID=1 reached FlipThreshold, so its next kitty reserves twice the stake.
+		assert_eq!(KittiesModule::current_stake_for(&1), 20_000);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_eq!(Balances::reserved_balance(1), 20_000);
+		assert_eq!(KittyStake::<Test>::get(3), Some(20_000));
+		// The escalated stake is released in full.
+		assert_ok!(KittiesModule::burn(Origin::signed(1), 3));
+		assert_eq!(Balances::reserved_balance(1), 0);
+	});
+}
+
+#[test]
+fn stake_escalated_after_rapid_sales() {
+	new_test_ext().execute_with(|| {
+		for kitty_id in 0..3 {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::sell(Origin::signed(1), kitty_id, Some(100), 0, None));
+			assert_ok!(KittiesModule::buy(Origin::signed(2), kitty_id));
+		}
+		assert_eq!(KittiesModule::recent_flips(&1), 3);
+		// Buying a kitty back needs the escalated stake as well, once it can be relisted within the FlipWindow.
+		System::set_block_number(6);
+		assert_ok!(KittiesModule::sell(Origin::signed(2), 0, Some(100), 0, None));
+		assert_ok!(KittiesModule::buy(Origin::signed(1), 0));
+		assert_eq!(Balances::reserved_balance(1), 20_000);
+		assert_eq!(KittyStake::<Test>::get(0), Some(20_000));
+	});
+}
+
+#[test]
+fn flips_reset_after_flip_window() {
+	new_test_ext().execute_with(|| {
+		for kitty_id in 0..3 {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, kitty_id));
+		}
+		assert_eq!(KittiesModule::current_stake_for(&1), 20_000);
+		// FlipWindow=10 blocks after the first flip at block 1.
+		System::set_block_number(11);
+		assert_eq!(KittiesModule::recent_flips(&1), 0);
+		assert_eq!(KittiesModule::current_stake_for(&1), 10_000);
+		// The next flip starts a new window.
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 3));
+		assert_eq!(KittiesModule::flips(&1), Some((1, 11)));
+	});
+}
+
+#[test]
+fn mint_for_works() {
+	new_test_ext().execute_with(|| {
+		let free_1 = Balances::free_balance(1);
+		let free_2 = Balances::free_balance(2);
+		assert_ok!(KittiesModule::mint_for(Origin::signed(1), 2));
+		// AccountID=2 owns the kitty and holds the stake, funded by AccountID=1.
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		assert_eq!(OwnedKitties::<Test>::get(2).to_vec(), vec![0]);
+		assert_eq!(Balances::free_balance(1), free_1 - 10_000);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(2), free_2);
+		assert_eq!(Balances::reserved_balance(2), 10_000);
+		assert_has_event!(Event::<Test>::KittyCreated(2, 0));
+	});
+}
+
+#[test]
+fn mint_for_failed_when_not_enough_balance() {
+	new_test_ext().execute_with(|| {
+		// AccountID=3 has only 9_000, less than the stake.
+		assert_noop!(KittiesModule::mint_for(Origin::signed(3), 2), Error::<Test>::NotEnoughBalanceForStaking);
+	});
+}
+
+#[test]
+fn genesis_kitties_are_preloaded() {
+	new_test_ext_with_kitties(vec![(1, [1u8; 16]), (2, [2u8; 16])]).execute_with(|| {
+		assert_eq!(KittiesCount::<Test>::get(), Some(2));
+		assert_eq!(Owner::<Test>::get(0), Some(1));
+		assert_eq!(Owner::<Test>::get(1), Some(2));
+		assert_eq!(KittiesModule::dnas_of(vec![0, 1]), vec![Some([1u8; 16]), Some([2u8; 16])]);
+		assert_eq!(OwnedKitties::<Test>::get(1).to_vec(), vec![0]);
+		assert_eq!(OwnedKitties::<Test>::get(2).to_vec(), vec![1]);
+		assert_eq!(KittiesModule::distinct_owners(), 2);
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert_eq!(Balances::reserved_balance(2), 10_000);
+		// Minting continues after the preloaded kitties.
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_eq!(Owner::<Test>::get(2), Some(1));
+	});
+}
+
+#[test]
+#[should_panic(expected = "genesis kitty owner must have StakeForEachKitty free to reserve")]
+fn genesis_kitty_owner_without_stake_panics() {
+	// AccountID=4 has no balance.
+	new_test_ext_with_kitties(vec![(4, [1u8; 16])]);
+}
+
+#[test]
+fn queued_breeds_processed_by_tip() {
+	new_test_ext().execute_with(|| {
+		// Reach MaxMintsPerBlock=10 in block 1.
+		for _ in 0..10 {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+		}
+		for kitty_id in 0..6 {
+			set_gender(kitty_id, if kitty_id % 2 == 0 { Gender::Male } else { Gender::Female });
+		}
+		for kitty_id in 2..6 {
+			assert_ok!(KittiesModule::set_breed_price(Origin::signed(1), kitty_id, Some(0)));
+		}
+		Balances::make_free_balance_be(&4, 100_000);
+		Balances::make_free_balance_be(&KittiesModule::account_id(), 1_000);
+		assert_ok!(KittiesModule::queue_breed(Origin::signed(1), 0, 1, 100));
+		assert_ok!(KittiesModule::queue_breed(Origin::signed(2), 2, 3, 300));
+		assert_ok!(KittiesModule::queue_breed(Origin::signed(4), 4, 5, 200));
+		assert_has_event!(Event::<Test>::BreedQueued(2, 2, 3, 300));
+		// The stake and the tip are reserved while queued.
+		assert_eq!(Balances::reserved_balance(4), 10_200);
+		// The highest tip breeds first.
+		System::set_block_number(2);
+		KittiesModule::on_initialize(2);
+		assert_eq!(Owner::<Test>::get(10), Some(2));
+		assert_eq!(Owner::<Test>::get(11), Some(4));
+		assert_eq!(Owner::<Test>::get(12), Some(1));
+		assert!(KittiesModule::breed_queue().is_empty());
+		// The tip went to the pallet pool, the stake stays reserved for the kitty.
+		assert_eq!(Balances::reserved_balance(4), 10_000);
+		assert_eq!(Balances::free_balance(KittiesModule::account_id()), 1_600);
+	});
+}
+
+#[test]
+fn queued_breeds_processed_up_to_mint_cap() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..10 {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+		}
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(1), 0, Some(0)));
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(1), 1, Some(0)));
+		for _ in 0..12 {
+			assert_ok!(KittiesModule::queue_breed(Origin::signed(2), 0, 1, 0));
+		}
+		System::set_block_number(2);
+		KittiesModule::on_initialize(2);
+		assert_eq!(MintsThisBlock::<Test>::get(), 10);
+		assert_eq!(KittiesCount::<Test>::get(), Some(20));
+		assert_eq!(KittiesModule::breed_queue().len(), 2);
+		// The rest waits for the next block.
+		System::set_block_number(3);
+		KittiesModule::on_initialize(3);
+		assert_eq!(KittiesCount::<Test>::get(), Some(22));
+		assert!(KittiesModule::breed_queue().is_empty());
+	});
+}
+
+#[test]
+fn queued_breed_failed_refunds_stake_and_tip() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(1), 0, Some(0)));
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(1), 1, Some(0)));
+		let free = Balances::free_balance(2);
+		assert_ok!(KittiesModule::queue_breed(Origin::signed(2), 0, 1, 100));
+		// A parent is burned before the queue is processed.
+		assert_ok!(KittiesModule::burn(Origin::signed(1), 0));
+		System::set_block_number(2);
+		KittiesModule::on_initialize(2);
+		assert_has_event!(Event::<Test>::QueuedBreedFailed(2, 0, 1));
+		assert_eq!(Balances::free_balance(2), free);
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(KittiesCount::<Test>::get(), Some(2));
+	});
+}
+
+#[test]
+fn queue_breed_failed_when_queue_full() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(1), 0, Some(0)));
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(1), 1, Some(0)));
+		// MaxQueuedBreeds=20.
+		for _ in 0..20 {
+			assert_ok!(KittiesModule::queue_breed(Origin::signed(2), 0, 1, 0));
+		}
+		assert_noop!(KittiesModule::queue_breed(Origin::signed(2), 0, 1, 0), Error::<Test>::BreedQueueFull);
+	});
+}
+
+#[test]
+fn breed_failed_when_same_gender_parents() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		set_gender(0, Gender::Female);
+		set_gender(1, Gender::Female);
+		// The pair is checked before the stud listing of kitty 1.
+		assert_noop!(KittiesModule::breed(Origin::signed(1), 0, 1), Error::<Test>::SameGenderParents);
+		assert_noop!(KittiesModule::queue_breed(Origin::signed(1), 0, 1, 0), Error::<Test>::SameGenderParents);
+		// Mixed genders breed fine once kitty 1 is a stud.
+		set_gender(1, Gender::Male);
+		assert_noop!(KittiesModule::breed(Origin::signed(1), 0, 1), Error::<Test>::NotStud);
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(2), 1, Some(0)));
+		assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+	});
+}
+
+#[test]
+fn gender_follows_dna() {
+	assert_eq!(Kitty::new([2u8; 16]).gender, Gender::Male);
+	assert_eq!(Kitty::new([3u8; 16]).gender, Gender::Female);
+}
+
+#[test]
+fn kitties_with_trait_works() {
+	new_test_ext().execute_with(|| {
+		// Kitties index=0,1,2 with known DNA, the first two sharing eyes(trait 1)=7.
+		for dna in [[7u8; 16], [9u8, 7, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9, 9], [9u8; 16]].iter() {
+			assert_ok!(KittiesModule::reserve_index(Origin::signed(1)));
+			let kitty_id = KittiesCount::<Test>::get().unwrap() - 1;
+			assert_ok!(KittiesModule::mint_reserved(Origin::signed(1), kitty_id, *dna));
+		}
+		assert_eq!(KittiesModule::decode_traits(&[7u8; 16]), [7u8; 5]);
+		let mut eyes = KittiesModule::kitties_with_trait((1, 7), 10);
+		eyes.sort();
+		assert_eq!(eyes, vec![0, 1]);
+		assert_eq!(KittiesModule::kitties_with_trait((0, 9), 10).len(), 2);
+		assert_eq!(KittiesModule::kitties_with_trait((0, 9), 1).len(), 1);
+		assert!(KittiesModule::kitties_with_trait((0, 8), 10).is_empty());
+		// A burned kitty leaves the index.
+		assert_ok!(KittiesModule::burn(Origin::signed(1), 0));
+		assert_eq!(KittiesModule::kitties_with_trait((1, 7), 10), vec![1]);
+	});
+}
+
+#[test]
+fn create_failed_when_exceed_max_kitties_owned() {
+	new_test_ext().execute_with(|| {
+		// MaxKittiesOwned=20, minted over two blocks under MaxMintsPerBlock=10.
+		for block in 1..=2 {
+			System::set_block_number(block);
+			KittiesModule::on_initialize(block);
+			for _ in 0..10 {
+				assert_ok!(KittiesModule::create(Origin::signed(1)));
+			}
+		}
+		assert_eq!(OwnedKitties::<Test>::get(1).len(), 20);
+		System::set_block_number(3);
+		KittiesModule::on_initialize(3);
+		let reserved = Balances::reserved_balance(1);
+		assert_noop!(KittiesModule::create(Origin::signed(1)), Error::<Test>::ExceedMaxKittiesOwned);
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_noop!(KittiesModule::breed(Origin::signed(1), 0, 1), Error::<Test>::ExceedMaxKittiesOwned);
+		assert_eq!(Balances::reserved_balance(1), reserved);
+	});
+}
+
+#[test]
+fn transfer_failed_when_exceed_max_kitties_owned() {
+	new_test_ext().execute_with(|| {
+		for block in 1..=2 {
+			System::set_block_number(block);
+			KittiesModule::on_initialize(block);
+			for _ in 0..10 {
+				assert_ok!(KittiesModule::create(Origin::signed(1)));
+			}
+		}
+		System::set_block_number(3);
+		KittiesModule::on_initialize(3);
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		// AccountID=1 is at the cap, so it can't receive or buy another kitty.
+		let reserved = Balances::reserved_balance(1);
+		assert_noop!(KittiesModule::transfer(Origin::signed(2), 1, 20), Error::<Test>::ExceedMaxKittiesOwned);
+		assert_ok!(KittiesModule::sell(Origin::signed(2), 20, Some(100), 0, None));
+		assert_noop!(KittiesModule::buy(Origin::signed(1), 20), Error::<Test>::ExceedMaxKittiesOwned);
+		assert_eq!(Balances::reserved_balance(1), reserved);
+		// Once one kitty leaves, there is room again.
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert_ok!(KittiesModule::transfer(Origin::signed(2), 1, 20));
+		assert_eq!(OwnedKitties::<Test>::get(1).len(), 20);
+	});
+}
+
+// Create the SaleAsset=0 and give AccountID=2 some of it.
+fn setup_sale_asset(amount: u128) {
+	assert_ok!(Assets::force_create(Origin::root(), 0, 1, true, 1));
+	assert_ok!(Assets::mint(Origin::signed(1), 0, 2, amount));
+}
+
+#[test]
+fn buy_with_mixed_price_works() {
+	new_test_ext().execute_with(|| {
+		setup_sale_asset(1_000);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell_mixed(Origin::signed(1), 0, 1_500, 300, 0));
+		assert_has_event!(Event::<Test>::AssetPriceSet(0, 300));
+		let free_1 = Balances::free_balance(1);
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		// Both legs reach the seller.
+		assert_eq!(Balances::free_balance(1), free_1 + 1_500 + 10_000);
+		assert_eq!(Assets::balance(0, 1), 300);
+		assert_eq!(Assets::balance(0, 2), 700);
+		assert_eq!(ListingAssetPrice::<Test>::get(0), 0);
+	});
+}
+
+#[test]
+fn buy_with_mixed_price_failed_when_asset_leg_fails() {
+	new_test_ext().execute_with(|| {
+		setup_sale_asset(100);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell_mixed(Origin::signed(1), 0, 1_500, 300, 0));
+		// AccountID=2 can pay the native leg but not the asset leg, so neither is paid.
+		assert_noop!(KittiesModule::buy(Origin::signed(2), 0), Error::<Test>::MixedPaymentFailed);
+		assert_eq!(Owner::<Test>::get(0), Some(1));
+		assert_eq!(Assets::balance(0, 2), 100);
+	});
+}
+
+#[test]
+fn sell_drops_asset_price() {
+	new_test_ext().execute_with(|| {
+		setup_sale_asset(100);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell_mixed(Origin::signed(1), 0, 1_500, 300, 0));
+		// Relisting with sell() asks for the native price only.
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
+		assert_eq!(ListingAssetPrice::<Test>::get(0), 0);
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		assert_eq!(Assets::balance(0, 2), 100);
+	});
+}
+
+#[test]
+fn buyable_by_checks_asset_price() {
+	new_test_ext().execute_with(|| {
+		setup_sale_asset(100);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell_mixed(Origin::signed(1), 0, 1_500, 300, 0));
+		// AccountID=2 can pay the native leg but not the asset leg.
+		assert_eq!(KittiesModule::buyable_by(&2, 10), Vec::<u32>::new());
+		assert_ok!(Assets::mint(Origin::signed(1), 0, 2, 200));
+		assert_eq!(KittiesModule::buyable_by(&2, 10), vec![0]);
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+	});
+}
+
+#[test]
+fn sponsor_stake_works() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&4, 100_000);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sponsor_stake(Origin::signed(4), 0));
+		assert_has_event!(Event::<Test>::StakeSponsored(4, 1, 0));
+		assert_eq!(KittiesModule::stake_sponsor(0), Some(4));
+		// The sponsor holds the stake and the owner's reserve is released.
+		assert_eq!(Balances::reserved_balance(4), 10_000);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		// The owner still lists its kitty.
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
+	});
+}
+
+#[test]
+fn sponsored_stake_returns_to_sponsor_on_transfer() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&4, 100_000);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sponsor_stake(Origin::signed(4), 0));
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert_eq!(Balances::reserved_balance(4), 0);
+		assert_eq!(Balances::free_balance(4), 100_000);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		// The new owner stakes for itself, with the NewOwnerRebate.
+		assert_eq!(Balances::reserved_balance(2), 9_500);
+		assert_eq!(KittiesModule::stake_sponsor(0), None);
+	});
+}
+
+#[test]
+fn sponsored_stake_returns_to_sponsor_on_burn() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&4, 100_000);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sponsor_stake(Origin::signed(4), 0));
+		assert_ok!(KittiesModule::burn(Origin::signed(1), 0));
+		assert_eq!(Balances::reserved_balance(4), 0);
+		assert_eq!(Balances::free_balance(4), 100_000);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(KittiesModule::stake_sponsor(0), None);
+	});
+}
+
+#[test]
+fn sponsor_stake_failed_when_invalid() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&4, 100_000);
+		assert_noop!(KittiesModule::sponsor_stake(Origin::signed(4), 0), Error::<Test>::InvalidKittyIndex);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::sponsor_stake(Origin::signed(1), 0), Error::<Test>::SponsorIsOwner);
+		assert_noop!(KittiesModule::sponsor_stake(Origin::signed(3), 0), Error::<Test>::NotEnoughBalanceForStaking);
+		assert_ok!(KittiesModule::sponsor_stake(Origin::signed(4), 0));
+		assert_noop!(KittiesModule::sponsor_stake(Origin::signed(2), 0), Error::<Test>::AlreadySponsored);
+	});
+}
+
+#[test]
+fn bid_refunds_outbid_bidder() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&4, 100_000);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::start_auction(Origin::signed(1), 0, 1_000, 5));
+		assert_has_event!(Event::<Test>::AuctionStarted(1, 0, 1_000, 6));
+		assert_noop!(KittiesModule::bid(Origin::signed(2), 0, 500), Error::<Test>::BidTooLow);
+		assert_ok!(KittiesModule::bid(Origin::signed(2), 0, 1_000));
+		assert_eq!(Balances::reserved_balance(2), 1_000);
+		assert_ok!(KittiesModule::bid(Origin::signed(4), 0, 1_500));
+		assert_has_event!(Event::<Test>::BidPlaced(4, 0, 1_500));
+		// The outbid bidder gets its reserve back.
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Balances::reserved_balance(4), 1_500);
+		assert_noop!(KittiesModule::bid(Origin::signed(2), 0, 1_500), Error::<Test>::BidTooLow);
+		assert_noop!(KittiesModule::bid(Origin::signed(1), 0, 2_000), Error::<Test>::BuyerIsOwner);
+	});
+}
+
+#[test]
+fn auction_settled_at_end() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&4, 100_000);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::start_auction(Origin::signed(1), 0, 1_000, 5));
+		assert_ok!(KittiesModule::bid(Origin::signed(4), 0, 1_500));
+		assert_ok!(KittiesModule::bid(Origin::signed(2), 0, 2_000));
+		let free_1 = Balances::free_balance(1);
+		System::set_block_number(6);
+		assert_noop!(KittiesModule::bid(Origin::signed(4), 0, 3_000), Error::<Test>::AuctionEnded);
+		KittiesModule::on_initialize(6);
+		assert_has_event!(Event::<Test>::AuctionSettled(0, Some((2, 2_000))));
+		// The winner pays its bid and stakes for the kitty, the seller gets the bid and its stake back.
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		assert_eq!(Balances::reserved_balance(2), 10_000);
+		assert_eq!(Balances::free_balance(1), free_1 + 2_000 + 10_000);
+		assert_eq!(Balances::reserved_balance(4), 0);
+		assert!(KittiesModule::auctions(0).is_none());
+		assert!(KittiesModule::auctions_ending(6).is_empty());
+	});
+}
+
+#[test]
+fn auction_without_bids_keeps_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::start_auction(Origin::signed(1), 0, 1_000, 5));
+		System::set_block_number(6);
+		KittiesModule::on_initialize(6);
+		assert_has_event!(Event::<Test>::AuctionSettled(0, None));
+		assert_eq!(Owner::<Test>::get(0), Some(1));
+		assert!(KittiesModule::auctions(0).is_none());
+		// The kitty can be sold again.
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
+	});
+}
+
+#[test]
+fn auction_and_sale_exclude_each_other() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
+		assert_noop!(KittiesModule::start_auction(Origin::signed(1), 0, 1_000, 5), Error::<Test>::KittyListedForSale);
+		assert_ok!(KittiesModule::start_auction(Origin::signed(1), 1, 1_000, 5));
+		assert_noop!(KittiesModule::sell(Origin::signed(1), 1, Some(1_500), 0, None), Error::<Test>::KittyInAuction);
+		assert_noop!(KittiesModule::transfer(Origin::signed(1), 2, 1), Error::<Test>::KittyInAuction);
+		assert_noop!(KittiesModule::start_auction(Origin::signed(1), 1, 1_000, 5), Error::<Test>::KittyInAuction);
+	});
+}
+
+#[test]
+fn start_auction_failed_when_invalid() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..3 {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+		}
+		assert_noop!(KittiesModule::start_auction(Origin::signed(2), 0, 1_000, 5), Error::<Test>::NotOwner);
+		assert_noop!(KittiesModule::start_auction(Origin::signed(1), 0, 1_000, 0), Error::<Test>::InvalidAuctionDuration);
+		assert_noop!(KittiesModule::bid(Origin::signed(2), 0, 1_000), Error::<Test>::NotInAuction);
+		// MaxAuctionsEnding=2 auctions can end in block 6.
+		assert_ok!(KittiesModule::start_auction(Origin::signed(1), 0, 1_000, 5));
+		assert_ok!(KittiesModule::start_auction(Origin::signed(1), 1, 1_000, 5));
+		assert_noop!(KittiesModule::start_auction(Origin::signed(1), 2, 1_000, 5), Error::<Test>::TooManyAuctionsEnding);
+	});
+}
+
+#[test]
+fn sell_failed_within_post_buy_lock() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		assert_eq!(KittiesModule::listing_locked_until(0), Some(6));
+		assert_noop!(KittiesModule::sell(Origin::signed(2), 0, Some(3_000), 0, None), Error::<Test>::PostBuyLockActive);
+		assert_noop!(KittiesModule::start_auction(Origin::signed(2), 0, 3_000, 5), Error::<Test>::PostBuyLockActive);
+		// Unlisting is fine, and handing the kitty on doesn't lift the lock.
+		assert_ok!(KittiesModule::sell(Origin::signed(2), 0, None, 0, None));
+		assert_ok!(KittiesModule::transfer(Origin::signed(2), 1, 0));
+		System::set_block_number(5);
+		assert_noop!(KittiesModule::sell(Origin::signed(1), 0, Some(3_000), 0, None), Error::<Test>::PostBuyLockActive);
+	});
+}
+
+#[test]
+fn sell_works_after_post_buy_lock() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		System::set_block_number(6);
+		assert_ok!(KittiesModule::sell(Origin::signed(2), 0, Some(3_000), 0, None));
+		assert_has_event!(Event::<Test>::KittyListed(2, 0, Some(3_000)));
+	});
+}
+
+#[test]
+fn first_sale_by_creator_pays_no_royalty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_eq!(KittiesModule::kitty_creator(0), Some(1));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(2_000), 0, None));
+		let seller_free = Balances::free_balance(1);
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		assert_eq!(Balances::free_balance(1), seller_free + 10_000 + 2_000);
+		assert!(!System::events().iter().any(|record| matches!(record.event, TestEvent::KittiesModule(Event::<Test>::RoyaltyPaid(..)))));
+	});
+}
+
+#[test]
+fn resale_pays_royalty_to_creator() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&4, 100_000);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(2_000), 0, None));
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		System::set_block_number(6);
+		assert_ok!(KittiesModule::sell(Origin::signed(2), 0, Some(2_000), 0, None));
+		let creator_free = Balances::free_balance(1);
+		let seller_free = Balances::free_balance(2);
+		assert_ok!(KittiesModule::buy(Origin::signed(4), 0));
+		// RoyaltyPercent=5% of the price goes to the creator, the rest to the seller.
+		assert_eq!(Balances::free_balance(1), creator_free + 100);
+		assert_eq!(Balances::free_balance(2), seller_free + 10_000 + 1_900);
+		assert_has_event!(Event::<Test>::RoyaltyPaid(1, 0, 100));
+	});
+}
+
+#[test]
+fn platform_fee_and_royalty_never_exceed_the_price() {
+	new_test_ext().execute_with(|| {
+		// RoyaltyPercent=5% leaves at most 95% for the platform.
+		assert_noop!(
+			KittiesModule::set_platform_fee(Origin::root(), Percent::from_percent(96), 3),
+			Error::<Test>::PlatformFeeTooHigh
+		);
+		assert_ok!(KittiesModule::set_platform_fee(Origin::root(), Percent::from_percent(95), 3));
+		// A fee stored before the check, together with the royalty, adds up to more than the price.
+		PlatformFee::<Test>::put(Some((Percent::from_percent(100), 3)));
+		Balances::make_free_balance_be(&4, 100_000);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert_ok!(KittiesModule::sell(Origin::signed(2), 0, Some(2_000), 0, None));
+		let creator_free = Balances::free_balance(1);
+		assert_ok!(KittiesModule::buy(Origin::signed(4), 0));
+		// The buyer pays the price and the stake only, and the royalty gets what the fee left.
+		assert_eq!(Balances::free_balance(4), 100_000 - 2_000 - 10_000);
+		assert_eq!(Balances::free_balance(3), 9_000 + 2_000);
+		assert_eq!(Balances::free_balance(1), creator_free);
+	});
+}
+
+#[test]
+fn shared_lineage_works() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&4, 100_000);
+		// Parents from AccountID=1 and AccountID=2 bred by AccountID=4 into kitty 2.
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(1), 0, Some(0)));
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(2), 1, Some(0)));
+		assert_ok!(KittiesModule::breed(Origin::signed(4), 0, 1));
+		assert_eq!(KittiesModule::kitty_parents(2), Some((0, 1)));
+		// Kitty 2 and a kitty of AccountID=4 bred into grandchild 4.
+		assert_ok!(KittiesModule::create(Origin::signed(4)));
+		set_gender(2, Gender::Male);
+		set_gender(3, Gender::Female);
+		assert_ok!(KittiesModule::breed(Origin::signed(4), 2, 3));
+		assert_eq!(KittiesModule::shared_lineage(&1, &2, 1), vec![2]);
+		assert_eq!(KittiesModule::shared_lineage(&1, &2, 2), vec![2, 4]);
+		// AccountID=1 is only a grandparent's owner of kitty 4.
+		assert!(KittiesModule::shared_lineage(&1, &4, 1).is_empty());
+		assert_eq!(KittiesModule::shared_lineage(&1, &4, 2), vec![4]);
+		assert!(KittiesModule::shared_lineage(&1, &3, 2).is_empty());
+		// A parent sold on still counts for its creator.
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 4, 0));
+		assert_eq!(KittiesModule::shared_lineage(&1, &2, 1), vec![2]);
+	});
+}
+
+#[test]
+fn transfer_failed_when_to_self() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::transfer(Origin::signed(1), 1, 0), Error::<Test>::TransferToSelf);
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert_eq!(KittiesModule::recent_flips(&1), 0);
+	});
+}
+
+#[test]
+fn stake_pool_funding_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::fund_stake_pool(Origin::signed(1), 5_000));
+		assert_has_event!(Event::<Test>::StakePoolFunded(1, 5_000));
+		assert_eq!(KittiesModule::stake_pool(1), 5_000);
+		assert_eq!(Balances::reserved_balance(1), 5_000);
+		assert_noop!(KittiesModule::withdraw_stake_pool(Origin::signed(1), 6_000), Error::<Test>::InsufficientStakePool);
+		assert_ok!(KittiesModule::withdraw_stake_pool(Origin::signed(1), 5_000));
+		assert_eq!(KittiesModule::stake_pool(1), 0);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_noop!(KittiesModule::fund_stake_pool(Origin::signed(3), 10_000), Error::<Test>::NotEnoughBalanceForStaking);
+	});
+}
+
+#[test]
+fn transfer_draws_stake_from_pool() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&4, 20_000);
+		assert_ok!(KittiesModule::fund_stake_pool(Origin::signed(4), 15_000));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		// 5_000 free can't cover the 9_500 stake, so it comes from the pool.
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 4, 0));
+		assert_has_event!(Event::<Test>::StakeToppedUp(4, 9_500));
+		assert_eq!(KittiesModule::stake_pool(4), 5_500);
+		assert_eq!(Balances::free_balance(4), 5_000);
+		assert_eq!(Balances::reserved_balance(4), 15_000);
+		// The kitty's stake is released in full, not back into the pool.
+		assert_ok!(KittiesModule::burn(Origin::signed(4), 0));
+		assert_eq!(Balances::free_balance(4), 14_500);
+		assert_eq!(KittiesModule::stake_pool(4), 5_500);
+	});
+}
+
+#[test]
+fn transfer_failed_when_stake_pool_empty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		// AccountID=3 has 9_000 free and no pool for the 9_500 stake.
+		assert_noop!(KittiesModule::transfer(Origin::signed(1), 3, 0), Error::<Test>::NotEnoughBalanceForStaking);
+		assert_eq!(KittiesModule::stake_pool(3), 0);
+	});
+}
+
+#[test]
+fn migrate_to_v2_works() {
+	new_test_ext().execute_with(|| {
+		// A V1 chain: kitties stored as their DNA only, without a creator, traits, owner index or version marker.
+		let dna = [3u8; 16];
+		for (kitty_id, owner) in vec![(0u32, 1u64), (1, 1), (2, 2)] {
+			frame_support::storage::unhashed::put(&Kitties::<Test>::hashed_key_for(kitty_id), &Some(dna));
+			Owner::<Test>::insert(kitty_id, Some(owner));
+		}
+		KittiesCount::<Test>::put(3);
+		StorageVersion::<Test>::kill();
+		assert_eq!(KittiesModule::storage_version(), Releases::V1);
+		assert_eq!(KittiesModule::distinct_owners(), 0);
+
+		KittiesModule::on_runtime_upgrade();
+		assert_eq!(KittiesModule::storage_version(), Releases::V3);
+		let kitty = KittiesModule::kitties(0).unwrap();
+		assert_eq!(kitty.dna, dna);
+		assert_eq!(kitty.gender, Gender::Female);
+		assert_eq!(KittiesModule::kitty_creator(0), Some(1));
+		let mut with_trait = KittiesModule::kitties_with_trait((0, 3), 10);
+		with_trait.sort();
+		assert_eq!(with_trait, vec![0, 1, 2]);
+		let mut owned = KittiesModule::owned_kitties(1).into_inner();
+		owned.sort();
+		assert_eq!(owned, vec![0, 1]);
+		assert_eq!(KittiesModule::owned_kitties(2).into_inner(), vec![2]);
+		assert_eq!(KittiesModule::distinct_owners(), 2);
+
+		// Running it again changes nothing.
+		KittyCreator::<Test>::insert(0, Some(2));
+		KittiesModule::on_runtime_upgrade();
+		assert_eq!(KittiesModule::kitty_creator(0), Some(2));
+		assert_eq!(KittiesModule::kitties(0).unwrap().dna, dna);
+	});
+}
+
+#[test]
+fn genesis_starts_on_latest_storage_version() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(KittiesModule::storage_version(), Releases::V3);
+	});
+}
+
+#[test]
+fn sealed_dna_rejects_reroll() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		let dna = Kitties::<Test>::get(0).unwrap().dna;
+		assert_noop!(KittiesModule::seal_dna(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_ok!(KittiesModule::seal_dna(Origin::signed(1), 0));
+		assert_has_event!(Event::<Test>::DnaSealed(1, 0));
+		assert_noop!(KittiesModule::reroll(Origin::signed(1), 0), Error::<Test>::DnaSealed);
+		assert_noop!(KittiesModule::seal_dna(Origin::signed(1), 0), Error::<Test>::DnaSealed);
+		assert_eq!(Kitties::<Test>::get(0).unwrap().dna, dna);
+	});
+}
+
+#[test]
+fn sealed_kitty_still_trades_and_breeds() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_ok!(KittiesModule::seal_dna(Origin::signed(1), 0));
+		assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_500), 0, None));
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		// The seal stays with the kitty.
+		assert!(KittiesModule::is_dna_sealed(0));
+		assert_noop!(KittiesModule::reroll(Origin::signed(2), 0), Error::<Test>::DnaSealed);
+	});
+}
+
+#[test]
+fn stakes_held_under_named_reserve() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..3 {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+		}
+		let reserve_id = KittiesModule::reserve_id();
+		assert_eq!(Balances::reserved_balance_named(&reserve_id, &1), 3 * 10_000);
+		// A reserve made outside the pallet is left alone.
+		assert_ok!(<Balances as ReservableCurrency<_>>::reserve(&1, 1_000));
+		assert_ok!(KittiesModule::burn(Origin::signed(1), 0));
+		assert_eq!(Balances::reserved_balance_named(&reserve_id, &1), 2 * 10_000);
+		assert_eq!(Balances::reserved_balance(1), 2 * 10_000 + 1_000);
+	});
+}
+
+#[test]
+fn migrate_to_v3_moves_anonymous_reserves() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		// A V2 chain held the stake in an anonymous reserve.
+		let reserve_id = KittiesModule::reserve_id();
+		<Balances as NamedReservableCurrency<_>>::unreserve_named(&reserve_id, &1, 10_000);
+		assert_ok!(<Balances as ReservableCurrency<_>>::reserve(&1, 10_000));
+		StorageVersion::<Test>::put(Releases::V2);
+
+		KittiesModule::on_runtime_upgrade();
+		assert_eq!(KittiesModule::storage_version(), Releases::V3);
+		assert_eq!(Balances::reserved_balance_named(&reserve_id, &1), 10_000);
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert_ok!(KittiesModule::burn(Origin::signed(1), 0));
+		assert_eq!(Balances::reserved_balance(1), 0);
+	});
+}
+
+#[test]
+fn migrate_to_v3_moves_reservation_stakes() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::reserve_index(Origin::signed(1)));
+		// A V2 chain held the reservation's stake, here not StakeForEachKitty, in an anonymous reserve.
+		let reserve_id = KittiesModule::reserve_id();
+		<Balances as NamedReservableCurrency<_>>::unreserve_named(&reserve_id, &1, 10_000);
+		KittyStake::<Test>::insert(0, Some(12_000));
+		assert_ok!(<Balances as ReservableCurrency<_>>::reserve(&1, 12_000));
+		StorageVersion::<Test>::put(Releases::V2);
+
+		KittiesModule::on_runtime_upgrade();
+		assert_eq!(Balances::reserved_balance_named(&reserve_id, &1), 12_000);
+		assert_eq!(Balances::reserved_balance(1), 12_000);
+	});
+}
+
+#[test]
+fn breed_pays_breed_price_of_other_owners_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		// Kitty 0 of AccountID=1 isn't offered, so AccountID=2 can't breed with it.
+		assert_noop!(KittiesModule::breed(Origin::signed(2), 0, 1), Error::<Test>::NotStud);
+		assert_noop!(KittiesModule::queue_breed(Origin::signed(2), 0, 1, 0), Error::<Test>::NotStud);
+		assert_noop!(KittiesModule::set_breed_price(Origin::signed(2), 0, Some(1_000)), Error::<Test>::NotOwner);
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(1), 0, Some(1_000)));
+		assert_has_event!(Event::<Test>::StudListed(1, 0, Some(1_000)));
+		let owner_free = Balances::free_balance(1);
+		assert_ok!(KittiesModule::breed(Origin::signed(2), 0, 1));
+		assert_eq!(Balances::free_balance(1), owner_free + 1_000);
+		assert_has_event!(Event::<Test>::StudFeePaid(2, 1, 0, 1_000));
+		assert_eq!(Owner::<Test>::get(2), Some(2));
+		// Withdrawing the price closes breeding again.
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(1), 0, None));
+		assert_noop!(KittiesModule::breed(Origin::signed(2), 0, 1), Error::<Test>::NotStud);
+	});
+}
+
+#[test]
+fn queued_breed_pays_breed_price() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_ok!(KittiesModule::set_breed_price(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(KittiesModule::queue_breed(Origin::signed(2), 0, 1, 0));
+		let owner_free = Balances::free_balance(1);
+		System::set_block_number(2);
+		KittiesModule::on_initialize(2);
+		assert_eq!(Owner::<Test>::get(2), Some(2));
+		assert_eq!(Balances::free_balance(1), owner_free + 1_000);
+	});
+}
+
+#[test]
+fn kitty_metadata_records_provenance() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_eq!(KittiesModule::kitty_metadata(0), Some(Provenance { parents: None, generation: 0, birth_block: 1 }));
+		System::set_block_number(3);
+		assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+		assert_eq!(KittiesModule::kitty_metadata(2), Some(Provenance { parents: Some((0, 1)), generation: 1, birth_block: 3 }));
+		// A grandchild is one generation after its older parent.
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		set_gender(2, Gender::Male);
+		set_gender(3, Gender::Female);
+		assert_ok!(KittiesModule::breed(Origin::signed(1), 2, 3));
+		assert_eq!(KittiesModule::kitty_metadata(4).unwrap().generation, 2);
+		assert_ok!(KittiesModule::burn(Origin::signed(1), 4));
+		assert_eq!(KittiesModule::kitty_metadata(4), None);
+	});
+}
+
+#[test]
+fn approved_account_can_transfer_from() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::transfer_from(Origin::signed(2), 2, 0), Error::<Test>::NotOwner);
+		assert_ok!(KittiesModule::approve(Origin::signed(1), 0, Some(2)));
+		assert_has_event!(Event::<Test>::Approval(1, 0, Some(2)));
+		assert_ok!(KittiesModule::transfer_from(Origin::signed(2), 2, 0));
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_has_event!(Event::<Test>::KittyTransferred(1, 2, 0));
+		// The approval ends with the handover.
+		assert_eq!(KittiesModule::approvals(0), None);
+	});
+}
+
+#[test]
+fn approve_failed_when_not_owner_or_to_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::approve(Origin::signed(2), 0, Some(2)), Error::<Test>::NotOwner);
+		assert_noop!(KittiesModule::approve(Origin::signed(1), 0, Some(1)), Error::<Test>::ApproveToOwner);
+		assert_noop!(KittiesModule::approve(Origin::signed(1), 1, Some(2)), Error::<Test>::InvalidKittyIndex);
+		// Removing the approval takes it away from the delegate.
+		assert_ok!(KittiesModule::approve(Origin::signed(1), 0, Some(2)));
+		assert_ok!(KittiesModule::approve(Origin::signed(1), 0, None));
+		assert_noop!(KittiesModule::transfer_from(Origin::signed(2), 2, 0), Error::<Test>::NotOwner);
+	});
+}
+
+#[test]
+fn operator_can_list_and_transfer_all_kitties() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::set_approval_for_all(Origin::signed(1), 2, true));
+		assert_has_event!(Event::<Test>::ApprovalForAll(1, 2, true));
+		// Kitties acquired after the approval are covered too.
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell(Origin::signed(2), 1, Some(1_000), 0, None));
+		assert_has_event!(Event::<Test>::KittyListed(1, 1, Some(1_000)));
+		Balances::make_free_balance_be(&4, 1_000_000);
+		assert_ok!(KittiesModule::transfer_from(Origin::signed(2), 4, 0));
+		assert_eq!(Owner::<Test>::get(0), Some(4));
+		// Revoking the operator ends its rights.
+		assert_ok!(KittiesModule::set_approval_for_all(Origin::signed(1), 2, false));
+		assert_noop!(KittiesModule::sell(Origin::signed(2), 1, None, 0, None), Error::<Test>::NotOwner);
+		assert_noop!(KittiesModule::set_approval_for_all(Origin::signed(1), 1, true), Error::<Test>::ApproveToOwner);
+	});
+}
+
+#[test]
+fn breeding_cooldown_works() {
+	new_test_ext().execute_with(|| {
+		BreedingCooldown::set(5);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+		assert_eq!(KittiesModule::next_breed_at(0), Some(6));
+		assert_eq!(KittiesModule::next_breed_at(1), Some(6));
+		// The child isn't on cooldown, but either parent is.
+		set_gender(2, Gender::Male);
+		assert_noop!(KittiesModule::breed(Origin::signed(1), 2, 1), Error::<Test>::KittyOnCooldown);
+		System::set_block_number(5);
+		assert_noop!(KittiesModule::breed(Origin::signed(1), 0, 1), Error::<Test>::KittyOnCooldown);
+		System::set_block_number(6);
+		assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+		assert_eq!(KittiesModule::next_breed_at(0), Some(11));
+	});
+}
+
+#[test]
+fn breed_all_compatible_skips_parents_on_cooldown() {
+	new_test_ext().execute_with(|| {
+		BreedingCooldown::set(5);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		set_gender(2, Gender::Male);
+		set_gender(3, Gender::Female);
+		// Once (0, 1) is bred, only (2, 3) is left without a parent on cooldown.
+		assert_ok!(KittiesModule::breed_all_compatible(Origin::signed(1), 10));
+		assert_has_event!(Event::<Test>::BredAllCompatible(1, 2));
+		assert_eq!(KittiesModule::kitty_parents(5), Some((2, 3)));
+	});
+}
+
+#[test]
+fn accept_offer_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::make_offer(Origin::signed(1), 0, 1_000), Error::<Test>::BuyerIsOwner);
+		// The kitty isn't listed, and the offer is reserved.
+		assert_ok!(KittiesModule::make_offer(Origin::signed(2), 0, 1_000));
+		assert_has_event!(Event::<Test>::OfferMade(2, 0, 1_000));
+		assert_eq!(Balances::reserved_balance(2), 1_000);
+		assert_noop!(KittiesModule::accept_offer(Origin::signed(2), 0, 2), Error::<Test>::NotOwner);
+		assert_noop!(KittiesModule::accept_offer(Origin::signed(1), 0, 4), Error::<Test>::NoSuchOffer);
+		let seller_free = Balances::free_balance(1);
+		assert_ok!(KittiesModule::accept_offer(Origin::signed(1), 0, 2));
+		assert_has_event!(Event::<Test>::OfferAccepted(1, 2, 0, 1_000));
+		assert_has_event!(Event::<Test>::KittySold(1, 2, 0, 1_000));
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		// The offer paid the seller, the stake moved to the bidder.
+		assert_eq!(Balances::free_balance(1), seller_free + 1_000 + 10_000);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(2), 10_000);
+		assert_eq!(KittiesModule::offers(0, 2), None);
+	});
+}
+
+#[test]
+fn cancel_offer_refunds_it() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::make_offer(Origin::signed(2), 0, 1_000));
+		// A new offer replaces the earlier one.
+		assert_ok!(KittiesModule::make_offer(Origin::signed(2), 0, 3_000));
+		assert_eq!(Balances::reserved_balance(2), 3_000);
+		assert_ok!(KittiesModule::cancel_offer(Origin::signed(2), 0));
+		assert_has_event!(Event::<Test>::OfferCancelled(2, 0));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_noop!(KittiesModule::cancel_offer(Origin::signed(2), 0), Error::<Test>::NoSuchOffer);
+		assert_noop!(KittiesModule::accept_offer(Origin::signed(1), 0, 2), Error::<Test>::NoSuchOffer);
+	});
+}
+
+#[test]
+fn listings_pages_priced_kitties() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..4 {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+		}
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_000), 0, None));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 1, Some(2_000), 0, None));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 2, Some(3_000), 0, None));
+		// An unlisted kitty isn't returned.
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 2, None, 0, None));
+		let mut all = KittiesModule::listings(0, 10);
+		all.sort();
+		assert_eq!(all, vec![(0, 1_000), (1, 2_000)]);
+		let first = KittiesModule::listings(0, 1);
+		let second = KittiesModule::listings(1, 1);
+		assert_eq!(first.len(), 1);
+		assert_eq!(second.len(), 1);
+		assert_ne!(first, second);
+		assert!(KittiesModule::listings(2, 10).is_empty());
+	});
+}
+
+#[test]
+fn create_batch_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create_batch(Origin::signed(1), 3));
+		assert_eq!(KittiesCount::<Test>::get(), Some(3));
+		assert_eq!(Balances::reserved_balance(1), 30_000);
+		assert_has_event!(Event::<Test>::KittyCreated(1, 2));
+		// MaxBatchSize=5.
+		assert_noop!(KittiesModule::create_batch(Origin::signed(1), 6), Error::<Test>::BatchTooLarge);
+		assert_ok!(KittiesModule::create_batch(Origin::signed(2), 5));
+		// A batch going past MaxMintsPerBlock=10 creates nothing.
+		assert_noop!(KittiesModule::create_batch(Origin::signed(2), 3), Error::<Test>::MintRateLimited);
+		assert_eq!(KittiesCount::<Test>::get(), Some(8));
+	});
+}
+
+#[test]
+fn transfer_batch_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create_batch(Origin::signed(1), 3));
+		Balances::make_free_balance_be(&4, 1_000_000);
+		assert_ok!(KittiesModule::transfer_batch(Origin::signed(1), vec![(0, 2), (1, 4)]));
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		assert_eq!(Owner::<Test>::get(1), Some(4));
+		assert_has_event!(Event::<Test>::KittyTransferred(1, 4, 1));
+		// One kitty not owned rolls the whole batch back.
+		assert_noop!(KittiesModule::transfer_batch(Origin::signed(1), vec![(2, 2), (0, 4)]), Error::<Test>::NotOwner);
+		assert_eq!(Owner::<Test>::get(2), Some(1));
+	});
+}
+
+#[test]
+fn sell_batch_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create_batch(Origin::signed(1), 3));
+		assert_ok!(KittiesModule::sell_batch(Origin::signed(1), vec![(0, Some(1_000)), (1, Some(2_000))]));
+		assert_eq!(ListForSale::<Test>::get(1), Some(2_000));
+		assert_has_event!(Event::<Test>::KittyListed(1, 0, Some(1_000)));
+		assert_ok!(KittiesModule::sell_batch(Origin::signed(1), vec![(0, None)]));
+		assert_eq!(ListForSale::<Test>::get(0), None);
+		assert_noop!(KittiesModule::sell_batch(Origin::signed(2), vec![(2, Some(1_000))]), Error::<Test>::NotOwner);
+		assert_noop!(KittiesModule::sell_batch(Origin::signed(1), vec![(2, Some(1_000)); 6]), Error::<Test>::BatchTooLarge);
+	});
+}
+
+#[test]
+fn set_name_reserves_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::set_name(Origin::signed(2), 0, b"Tom".to_vec(), None), Error::<Test>::NotOwner);
+		assert_noop!(KittiesModule::set_name(Origin::signed(1), 0, vec![b'a'; 17], None), Error::<Test>::NameTooLong);
+		assert_noop!(KittiesModule::set_name(Origin::signed(1), 0, b"Tom".to_vec(), Some(vec![b'u'; 65])), Error::<Test>::UriTooLong);
+		// NameDepositPerByte=10, for the name and URI bytes.
+		assert_ok!(KittiesModule::set_name(Origin::signed(1), 0, b"Tom".to_vec(), Some(b"ipfs://x".to_vec())));
+		assert_has_event!(Event::<Test>::KittyNamed(1, 0, 110));
+		assert_eq!(Balances::reserved_balance(1), 10_000 + 110);
+		let name = KittiesModule::kitty_metadata_of(0).unwrap();
+		assert_eq!(name.name.into_inner(), b"Tom".to_vec());
+		assert_eq!(name.uri.map(|uri| uri.into_inner()), Some(b"ipfs://x".to_vec()));
+		// Renaming replaces the deposit.
+		assert_ok!(KittiesModule::set_name(Origin::signed(1), 0, b"Tommy".to_vec(), None));
+		assert_eq!(Balances::reserved_balance(1), 10_000 + 50);
+		assert_ok!(KittiesModule::clear_name(Origin::signed(1), 0));
+		assert_has_event!(Event::<Test>::KittyNameCleared(1, 0));
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert_noop!(KittiesModule::clear_name(Origin::signed(1), 0), Error::<Test>::NoKittyName);
+	});
+}
+
+#[test]
+fn kitty_name_stays_through_transfer_and_is_refunded_on_burn() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::set_name(Origin::signed(1), 0, b"Tom".to_vec(), None));
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert!(KittiesModule::kitty_metadata_of(0).is_some());
+		// The deposit stays with the account which paid it, until the new owner burns the kitty.
+		assert_eq!(Balances::reserved_balance(1), 30);
+		assert_ok!(KittiesModule::burn(Origin::signed(2), 0));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert!(KittiesModule::kitty_metadata_of(0).is_none());
+	});
+}
+
+#[test]
+fn expired_listing_can_not_be_bought() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::sell(Origin::signed(1), 0, Some(1_000), 0, Some(1)), Error::<Test>::InvalidListingExpiry);
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_000), 0, Some(5)));
+		assert_eq!(KittiesModule::listing_expiry(0), Some(5));
+		System::set_block_number(4);
+		assert!(KittiesModule::kitty_flags(0).listed);
+		System::set_block_number(5);
+		assert!(!KittiesModule::kitty_flags(0).listed);
+		assert!(KittiesModule::listings(0, 10).is_empty());
+		assert_noop!(KittiesModule::buy(Origin::signed(2), 0), Error::<Test>::ListingExpired);
+		// Relisting without expiry makes it buyable again.
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_000), 0, None));
+		assert_eq!(KittiesModule::listing_expiry(0), None);
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+	});
+}
+
+#[test]
+fn leased_kitty_breeds_for_tenant() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		set_gender(0, Gender::Male);
+		set_gender(1, Gender::Female);
+		assert_noop!(KittiesModule::lease(Origin::signed(1), 0, 2, 1_000, 0), Error::<Test>::InvalidLeaseDuration);
+		assert_ok!(KittiesModule::lease(Origin::signed(1), 0, 2, 1_000, 5));
+		assert_has_event!(Event::<Test>::LeaseOffered(1, 2, 0, 1_000, 5));
+		assert_noop!(KittiesModule::accept_lease(Origin::signed(4), 0), Error::<Test>::NotLeaseParty);
+		let owner_free = Balances::free_balance(1);
+		assert_ok!(KittiesModule::accept_lease(Origin::signed(2), 0));
+		assert_has_event!(Event::<Test>::LeaseStarted(2, 0, 6));
+		assert_eq!(Balances::free_balance(1), owner_free + 1_000);
+		// The tenant breeds without a stud fee, the owner keeps the kitty but can't move or breed it.
+		assert_ok!(KittiesModule::breed(Origin::signed(2), 0, 1));
+		assert_eq!(Owner::<Test>::get(2), Some(2));
+		assert_eq!(Owner::<Test>::get(0), Some(1));
+		assert_noop!(KittiesModule::transfer(Origin::signed(1), 4, 0), Error::<Test>::KittyLeased);
+		assert_noop!(KittiesModule::sell(Origin::signed(1), 0, Some(1_000), 0, None), Error::<Test>::KittyLeased);
+		assert_noop!(KittiesModule::end_lease(Origin::signed(1), 0), Error::<Test>::LeaseRunning);
+		// The lease ends by itself at its end block.
+		System::set_block_number(6);
+		KittiesModule::on_initialize(6);
+		assert_has_event!(Event::<Test>::LeaseEnded(0));
+		assert!(KittiesModule::leases(0).is_none());
+		assert_noop!(KittiesModule::breed(Origin::signed(2), 0, 1), Error::<Test>::NotStud);
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+	});
+}
+
+#[test]
+fn end_lease_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::end_lease(Origin::signed(1), 0), Error::<Test>::NoSuchLease);
+		// An offered lease can be withdrawn by the owner.
+		assert_ok!(KittiesModule::lease(Origin::signed(1), 0, 2, 1_000, 5));
+		assert_noop!(KittiesModule::end_lease(Origin::signed(4), 0), Error::<Test>::NotLeaseParty);
+		assert_ok!(KittiesModule::end_lease(Origin::signed(1), 0));
+		assert_noop!(KittiesModule::accept_lease(Origin::signed(2), 0), Error::<Test>::NoSuchLease);
+		// The tenant can end a running lease early.
+		assert_ok!(KittiesModule::lease(Origin::signed(1), 0, 2, 1_000, 5));
+		assert_ok!(KittiesModule::accept_lease(Origin::signed(2), 0));
+		assert_ok!(KittiesModule::end_lease(Origin::signed(2), 0));
+		assert_has_event!(Event::<Test>::LeaseEnded(0));
+		assert!(KittiesModule::leases_ending(6).is_empty());
+		assert_ok!(KittiesModule::burn(Origin::signed(1), 0));
+	});
+}
+
+#[test]
+fn two_step_transfer_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::initiate_transfer(Origin::signed(2), 0, 2), Error::<Test>::NotOwner);
+		assert_noop!(KittiesModule::initiate_transfer(Origin::signed(1), 0, 1), Error::<Test>::TransferToSelf);
+		assert_ok!(KittiesModule::initiate_transfer(Origin::signed(1), 0, 2));
+		assert_has_event!(Event::<Test>::TransferInitiated(1, 2, 0));
+		// Nothing changes until the recipient claims the kitty.
+		assert_eq!(Owner::<Test>::get(0), Some(1));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_noop!(KittiesModule::claim_transfer(Origin::signed(4), 0), Error::<Test>::NotTransferRecipient);
+		assert_ok!(KittiesModule::claim_transfer(Origin::signed(2), 0));
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(2), 9_500);
+		assert_has_event!(Event::<Test>::KittyTransferred(1, 2, 0));
+		assert_eq!(KittiesModule::pending_transfers(0), None);
+	});
+}
+
+#[test]
+fn cancel_transfer_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::cancel_transfer(Origin::signed(1), 0), Error::<Test>::NoPendingTransfer);
+		assert_ok!(KittiesModule::initiate_transfer(Origin::signed(1), 0, 2));
+		assert_noop!(KittiesModule::cancel_transfer(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_ok!(KittiesModule::cancel_transfer(Origin::signed(1), 0));
+		assert_has_event!(Event::<Test>::TransferCancelled(1, 0));
+		assert_noop!(KittiesModule::claim_transfer(Origin::signed(2), 0), Error::<Test>::NoPendingTransfer);
+		// A pending transfer is dropped when the kitty goes elsewhere.
+		assert_ok!(KittiesModule::initiate_transfer(Origin::signed(1), 0, 2));
+		Balances::make_free_balance_be(&4, 1_000_000);
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 4, 0));
+		assert_noop!(KittiesModule::claim_transfer(Origin::signed(2), 0), Error::<Test>::NoPendingTransfer);
+	});
+}
+
+frame_support::parameter_types! {
+	pub const NoMutation: u8 = 0;
+	pub const AlwaysMutate: u8 = 255;
+}
+
+#[test]
+fn bitmask_mixer_takes_bits_by_seed() {
+	let dna_1 = [0b1010_1010u8; 16];
+	let dna_2 = [0b0101_0101u8; 16];
+	assert_eq!(genetics::BitmaskMixer::combine(&dna_1, &dna_2, &[0xFF; 16]), dna_1);
+	assert_eq!(genetics::BitmaskMixer::combine(&dna_1, &dna_2, &[0x00; 16]), dna_2);
+	assert_eq!(genetics::BitmaskMixer::combine(&dna_1, &dna_2, &[0xF0; 16]), [0b1010_0101u8; 16]);
+}
+
+#[test]
+fn mutating_mixer_mutates_at_its_rate() {
+	let dna_1 = [1u8; 16];
+	let dna_2 = [2u8; 16];
+	let seed = [7u8; 16];
+	let mixed = genetics::BitmaskMixer::combine(&dna_1, &dna_2, &seed);
+	assert_eq!(genetics::MutatingMixer::<NoMutation>::combine(&dna_1, &dna_2, &seed), mixed);
+	let mutated = genetics::MutatingMixer::<AlwaysMutate>::combine(&dna_1, &dna_2, &seed);
+	assert_ne!(mutated, mixed);
+	// The same seed always gives the same child.
+	assert_eq!(genetics::MutatingMixer::<AlwaysMutate>::combine(&dna_1, &dna_2, &seed), mutated);
+}
+
+#[test]
+fn paused_market_rejects_market_calls() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_000), 0, None));
+		assert_noop!(KittiesModule::set_paused(Origin::signed(1), true), sp_runtime::DispatchError::BadOrigin);
+		assert_ok!(KittiesModule::set_paused(Origin::root(), true));
+		assert_has_event!(Event::<Test>::MarketPauseSet(true));
+		assert_noop!(KittiesModule::buy(Origin::signed(2), 0), Error::<Test>::MarketPaused);
+		assert_noop!(KittiesModule::sell(Origin::signed(1), 1, Some(1_000), 0, None), Error::<Test>::MarketPaused);
+		assert_noop!(KittiesModule::start_auction(Origin::signed(1), 1, 1_000, 5), Error::<Test>::MarketPaused);
+		assert_noop!(KittiesModule::make_offer(Origin::signed(2), 1, 1_000), Error::<Test>::MarketPaused);
+		// Unlisting still works.
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, None, 0, None));
+		assert_ok!(KittiesModule::set_paused(Origin::root(), false));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_000), 0, None));
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+	});
+}
+
+#[test]
+fn force_transfer_and_force_delist_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::force_transfer(Origin::signed(2), 0, 2), sp_runtime::DispatchError::BadOrigin);
+		assert_ok!(KittiesModule::force_transfer(Origin::root(), 0, 2));
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_has_event!(Event::<Test>::KittyForceTransferred(1, 2, 0));
+		assert_ok!(KittiesModule::sell(Origin::signed(2), 0, Some(1_000), 0, None));
+		assert_ok!(KittiesModule::force_delist(Origin::root(), 0));
+		assert_eq!(ListForSale::<Test>::get(0), None);
+		assert_has_event!(Event::<Test>::KittyDelisted(2, 0));
+		assert_noop!(KittiesModule::force_delist(Origin::root(), 0), Error::<Test>::NotForSale);
+	});
+}
+
+#[test]
+fn nonfungibles_inspect_and_transfer_work() {
+	use frame_support::traits::tokens::nonfungibles::{Inspect, Transfer};
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_eq!(<KittiesModule as Inspect<u64>>::owner(&(), &0), Some(1));
+		assert_eq!(<KittiesModule as Inspect<u64>>::owner(&(), &1), None);
+		assert_eq!(KittiesModule::attribute(&(), &0, b"dna"), Some(Kitties::<Test>::get(0).unwrap().dna.to_vec()));
+		assert_eq!(KittiesModule::attribute(&(), &0, b"name"), None);
+		assert_ok!(KittiesModule::set_name(Origin::signed(1), 0, b"Tom".to_vec(), None));
+		assert_eq!(KittiesModule::attribute(&(), &0, b"name"), Some(b"Tom".to_vec()));
+		assert_eq!(KittiesModule::attribute(&(), &0, b"color"), None);
+
+		assert!(KittiesModule::can_transfer(&(), &0));
+		assert_ok!(<KittiesModule as Transfer<u64>>::transfer(&(), &0, &2));
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		assert_has_event!(Event::<Test>::KittyTransferred(1, 2, 0));
+		assert_noop!(<KittiesModule as Transfer<u64>>::transfer(&(), &1, &2), Error::<Test>::InvalidKittyIndex);
+
+		assert_ok!(KittiesModule::start_auction(Origin::signed(2), 0, 1_000, 5));
+		assert!(!KittiesModule::can_transfer(&(), &0));
+	});
+}
+
+#[test]
+fn nonfungibles_mutate_works() {
+	use frame_support::traits::tokens::nonfungibles::Mutate;
+	new_test_ext().execute_with(|| {
+		assert_noop!(KittiesModule::mint_into(&(), &1, &1), Error::<Test>::InvalidKittyIndex);
+		assert_ok!(KittiesModule::mint_into(&(), &0, &1));
+		assert_eq!(Owner::<Test>::get(0), Some(1));
+		let stake = Balances::reserved_balance(1);
+		assert!(stake > 0);
+		assert_has_event!(Event::<Test>::KittyCreated(1, 0));
+
+		assert_ok!(KittiesModule::burn_from(&(), &0));
+		assert_eq!(Owner::<Test>::get(0), None);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_has_event!(Event::<Test>::KittyBurned(1, 0));
+		assert_noop!(KittiesModule::burn_from(&(), &0), Error::<Test>::InvalidKittyIndex);
+	});
+}
+
+#[test]
+fn sales_history_and_volume_are_recorded() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_eq!(KittiesModule::total_volume(), 0);
+		System::set_block_number(1);
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_000), 0, None));
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		System::set_block_number(6);
+		assert_ok!(KittiesModule::sell(Origin::signed(2), 0, Some(2_000), 0, None));
+		assert_ok!(KittiesModule::buy(Origin::signed(1), 0));
+		assert_eq!(KittiesModule::last_sales(0).into_inner(), vec![(1_000, 1), (2_000, 6)]);
+		// An auction sale counts too, and pushes out the oldest sale.
+		System::set_block_number(11);
+		assert_ok!(KittiesModule::start_auction(Origin::signed(1), 0, 1_000, 5));
+		assert_ok!(KittiesModule::bid(Origin::signed(2), 0, 3_000));
+		System::set_block_number(16);
+		KittiesModule::on_initialize(16);
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		assert_eq!(KittiesModule::last_sales(0).into_inner(), vec![(2_000, 6), (3_000, 16)]);
+		assert_eq!(KittiesModule::total_volume(), 6_000);
+		// Burning the kitty drops its history but not the volume.
+		assert_ok!(KittiesModule::burn(Origin::signed(2), 0));
+		assert!(KittiesModule::last_sales(0).is_empty());
+		assert_eq!(KittiesModule::total_volume(), 6_000);
+	});
+}
+
+#[test]
+fn floor_price_is_lowest_active_listing() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(KittiesModule::floor_price(), None);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(2)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(3_000), 0, None));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 1, Some(1_000), 0, Some(5)));
+		assert_ok!(KittiesModule::sell(Origin::signed(2), 2, Some(2_000), 0, None));
+		assert_eq!(KittiesModule::floor_price(), Some(1_000));
+		// Expired listings don't count.
+		System::set_block_number(5);
+		assert_eq!(KittiesModule::floor_price(), Some(2_000));
+		assert_ok!(KittiesModule::sell(Origin::signed(2), 2, None, 0, None));
+		assert_eq!(KittiesModule::floor_price(), Some(3_000));
+	});
+}
+
+#[test]
+fn requested_kitty_is_revealed_later() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::request_kitty(Origin::signed(1)));
+		assert_has_event!(Event::<Test>::KittyRequested(1, 0, 4));
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert_eq!(KittiesModule::pending_reveal(0), Some(4));
+		assert!(KittiesModule::kitties(0).is_none());
+		// The requester can't pick the DNA itself.
+		assert_noop!(KittiesModule::mint_reserved(Origin::signed(1), 0, [0u8; 16]), Error::<Test>::RevealPending);
+
+		System::set_block_number(4);
+		KittiesModule::on_initialize(4);
+		assert!(KittiesModule::kitties(0).is_some());
+		assert_eq!(Owner::<Test>::get(0), Some(1));
+		assert_eq!(KittiesModule::kitty_creator(0), Some(1));
+		assert_eq!(KittiesModule::pending_reveal(0), None);
+		assert!(KittiesModule::reveals_due(4).is_empty());
+		assert_has_event!(Event::<Test>::KittyCreated(1, 0));
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+	});
+}
+
+#[test]
+fn request_kitty_failed_when_too_many_reveals() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::request_kitty(Origin::signed(1)));
+		assert_ok!(KittiesModule::request_kitty(Origin::signed(2)));
+		assert_noop!(KittiesModule::request_kitty(Origin::signed(1)), Error::<Test>::TooManyReveals);
+		System::set_block_number(2);
+		assert_ok!(KittiesModule::request_kitty(Origin::signed(1)));
+		assert_eq!(KittiesModule::pending_reveal(2), Some(5));
+	});
+}
+
+#[test]
+fn bundle_is_sold_as_a_whole() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_noop!(KittiesModule::sell_bundle(Origin::signed(1), vec![], 5_000), Error::<Test>::EmptyBundle);
+		assert_noop!(KittiesModule::sell_bundle(Origin::signed(1), vec![0, 0], 5_000), Error::<Test>::KittyInBundle);
+		assert_noop!(KittiesModule::sell_bundle(Origin::signed(2), vec![0, 1], 5_000), Error::<Test>::NotOwner);
+		assert_ok!(KittiesModule::sell_bundle(Origin::signed(1), vec![0, 1], 5_000));
+		assert_has_event!(Event::<Test>::BundleListed(1, 0, 5_000));
+		assert_eq!(KittiesModule::kitty_bundle(0), Some(0));
+		// Bundled kitties can't be sold on their own.
+		assert_noop!(KittiesModule::sell(Origin::signed(1), 0, Some(1_000), 0, None), Error::<Test>::KittyInBundle);
+		assert_noop!(KittiesModule::start_auction(Origin::signed(1), 1, 1_000, 5), Error::<Test>::KittyInBundle);
+		assert_noop!(KittiesModule::sell_bundle(Origin::signed(1), vec![1, 2], 5_000), Error::<Test>::KittyInBundle);
+
+		assert_noop!(KittiesModule::buy_bundle(Origin::signed(1), 0), Error::<Test>::BuyerIsOwner);
+		assert_noop!(KittiesModule::buy_bundle(Origin::signed(2), 1), Error::<Test>::NoSuchBundle);
+		let free_1 = Balances::free_balance(1);
+		assert_ok!(KittiesModule::buy_bundle(Origin::signed(2), 0));
+		assert_has_event!(Event::<Test>::BundleSold(1, 2, 0, 5_000));
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		assert_eq!(Owner::<Test>::get(1), Some(2));
+		assert_eq!(Owner::<Test>::get(2), Some(1));
+		// The seller gets the price and both stakes back.
+		assert_eq!(Balances::free_balance(1), free_1 + 5_000 + 20_000);
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert!(KittiesModule::bundles(0).is_none());
+		assert_eq!(KittiesModule::kitty_bundle(0), None);
+		assert_eq!(KittiesModule::total_volume(), 5_000);
+	});
+}
+
+#[test]
+fn bundle_can_be_partly_cancelled() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell_bundle(Origin::signed(1), vec![0, 1, 2], 5_000));
+		assert_noop!(KittiesModule::cancel_bundle(Origin::signed(2), 0, None), Error::<Test>::NotOwner);
+		assert_ok!(KittiesModule::cancel_bundle(Origin::signed(1), 0, Some(vec![1])));
+		assert_has_event!(Event::<Test>::KittyRemovedFromBundle(0, 1));
+		assert_eq!(KittiesModule::bundles(0).unwrap().kitties.into_inner(), vec![0, 2]);
+		assert_eq!(KittiesModule::kitty_bundle(1), None);
+		assert_noop!(KittiesModule::cancel_bundle(Origin::signed(1), 0, Some(vec![1])), Error::<Test>::NotInBundle);
+		// The removed kitty can be sold on its own again.
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 1, Some(1_000), 0, None));
+		assert_ok!(KittiesModule::cancel_bundle(Origin::signed(1), 0, None));
+		assert_has_event!(Event::<Test>::BundleCancelled(0));
+		assert!(KittiesModule::bundles(0).is_none());
+		assert_eq!(KittiesModule::kitty_bundle(0), None);
+		assert_eq!(KittiesModule::kitty_bundle(2), None);
+	});
+}
+
+#[test]
+fn bundle_is_removed_when_a_kitty_changes_hands() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell_bundle(Origin::signed(1), vec![0, 1], 5_000));
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert_has_event!(Event::<Test>::BundleCancelled(0));
+		assert!(KittiesModule::bundles(0).is_none());
+		assert_eq!(KittiesModule::kitty_bundle(1), None);
+		assert_noop!(KittiesModule::buy_bundle(Origin::signed(2), 0), Error::<Test>::NoSuchBundle);
+	});
+}
+
+#[test]
+fn bundled_kitty_cannot_be_leased() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		// A lease offered before the kitty was bundled can't be accepted anymore.
+		assert_ok!(KittiesModule::lease(Origin::signed(1), 0, 2, 1_000, 5));
+		assert_ok!(KittiesModule::sell_bundle(Origin::signed(1), vec![0, 1], 5_000));
+		assert_noop!(KittiesModule::accept_lease(Origin::signed(2), 0), Error::<Test>::KittyInBundle);
+		assert_noop!(KittiesModule::lease(Origin::signed(1), 1, 2, 1_000, 5), Error::<Test>::KittyInBundle);
+		// The bundle still sells as a whole, and the offered lease doesn't follow the kitties.
+		assert_ok!(KittiesModule::buy_bundle(Origin::signed(2), 0));
+		assert_eq!(Owner::<Test>::get(0), Some(2));
+		assert_eq!(Owner::<Test>::get(1), Some(2));
+		assert!(KittiesModule::leases(0).is_none());
+	});
+}
+
+#[test]
+fn test_helpers_mint_deterministic_kitties() {
+	new_test_ext().execute_with(|| {
+		let ids = test_helpers::mint_many::<Test>(&1, 3).unwrap();
+		assert_eq!(ids, vec![0, 1, 2]);
+		assert_eq!(Kitties::<Test>::get(1).unwrap().dna, test_helpers::dna_of(Gender::Female, 1));
+		assert_eq!(Kitties::<Test>::get(2).unwrap().gender, Gender::Male);
+		assert_eq!(Balances::reserved_balance(1), 30_000);
+		assert_eq!(test_helpers::mint::<Test>(&2, [7u8; 16]), Ok(3));
+		assert_eq!(Owner::<Test>::get(3), Some(2));
+	});
+}
+
+#[test]
+fn try_state_holds_through_market_actions() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::try_state());
+		test_helpers::mint_many::<Test>(&1, 4).unwrap();
+		assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_000), 0, None));
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		assert_ok!(KittiesModule::sell_bundle(Origin::signed(1), vec![1, 2], 5_000));
+		assert_ok!(KittiesModule::make_offer(Origin::signed(2), 3, 1_000));
+		assert_ok!(KittiesModule::start_auction(Origin::signed(1), 4, 1_000, 5));
+		assert_ok!(KittiesModule::bid(Origin::signed(2), 4, 1_500));
+		assert_ok!(KittiesModule::reserve_index(Origin::signed(2)));
+		assert_ok!(KittiesModule::burn(Origin::signed(2), 0));
+		assert_ok!(KittiesModule::try_state());
+	});
+}
+
+#[test]
+fn try_state_detects_drifted_storage() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		Owner::<Test>::remove(0);
+		assert_eq!(KittiesModule::try_state(), Err("kitty without an owner"));
+	});
+}
+
+#[test]
+fn try_state_detects_uncovered_stakes() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		Balances::unreserve_named(&KittiesModule::reserve_id(), &1, 1);
+		assert_eq!(KittiesModule::try_state(), Err("reserve below the stakes held"));
+	});
+}
+
+#[test]
+fn bundle_sale_pays_royalties_and_records_each_kitty() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&4, 100_000);
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 1));
+		assert_ok!(KittiesModule::sell_bundle(Origin::signed(2), vec![0, 1], 5_001));
+		assert_ok!(KittiesModule::buy_bundle(Origin::signed(4), 0));
+		// Each kitty is sold for its share, the first one taking the remainder.
+		assert_has_event!(Event::<Test>::KittySold(2, 4, 0, 2_501));
+		assert_has_event!(Event::<Test>::KittySold(2, 4, 1, 2_500));
+		assert_has_event!(Event::<Test>::RoyaltyPaid(1, 0, 125));
+		assert_has_event!(Event::<Test>::RoyaltyPaid(1, 1, 125));
+		assert_eq!(KittiesModule::last_sales(0).into_inner(), vec![(2_501, 1)]);
+		assert_eq!(KittiesModule::total_volume(), 5_001);
+		// A sale is final, unlike a transfer.
+		assert_noop!(KittiesModule::reverse_transfer(Origin::root(), 0), Error::<Test>::NoTransferToReverse);
+		assert_eq!(Balances::reserved_balance(4), 20_000);
+		assert_eq!(KittiesModule::recent_flips(&2), 1);
+	});
+}
+
+#[test]
+fn burn_refunds_open_offers() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::make_offer(Origin::signed(2), 0, 1_000));
+		assert_eq!(Balances::reserved_balance(2), 1_000);
+		assert_ok!(KittiesModule::burn(Origin::signed(1), 0));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Offers::<Test>::get(0, 2), None);
+		assert_has_event!(Event::<Test>::OfferCancelled(2, 0));
+		assert_ok!(KittiesModule::try_state());
+	});
+}
+
+#[test]
+fn reservations_and_reversals_reserve_the_current_stake() {
+	new_test_ext().execute_with(|| {
+		for kitty_id in 0..3 {
+			assert_ok!(KittiesModule::create(Origin::signed(2)));
+			assert_ok!(KittiesModule::transfer(Origin::signed(2), 1, kitty_id));
+		}
+		// Account 2 is flipping, so its stakes are doubled.
+		assert_eq!(KittiesModule::current_stake_for(&2), 20_000);
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_ok!(KittiesModule::reserve_index(Origin::signed(2)));
+		assert_eq!(Balances::reserved_balance(2), 20_000);
+		assert_ok!(KittiesModule::mint_reserved(Origin::signed(2), 3, [3u8; 16]));
+		assert_ok!(KittiesModule::burn(Origin::signed(2), 3));
+		assert_eq!(Balances::reserved_balance(2), 0);
+
+		assert_ok!(KittiesModule::reverse_transfer(Origin::root(), 0));
+		assert_eq!(Balances::reserved_balance(2), 20_000);
+		assert_ok!(KittiesModule::burn(Origin::signed(2), 0));
+		assert_eq!(Balances::reserved_balance(2), 0);
+	});
+}
+
+#[test]
+fn reroll_keeps_post_buy_lock() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::sell(Origin::signed(1), 0, Some(1_000), 0, None));
+		assert_ok!(KittiesModule::buy(Origin::signed(2), 0));
+		assert_ok!(KittiesModule::reroll(Origin::signed(2), 0));
+		// Still within PostBuyLock=5 blocks of the purchase.
+		assert_noop!(KittiesModule::sell(Origin::signed(2), 1, Some(2_000), 0, None), Error::<Test>::PostBuyLockActive);
+		System::set_block_number(6);
+		assert_ok!(KittiesModule::sell(Origin::signed(2), 1, Some(2_000), 0, None));
+	});
+}
@@ -28,9 +28,9 @@ use sp_version::NativeVersion;
 pub use sp_runtime::BuildStorage;
 pub use pallet_timestamp::Call as TimestampCall;
 pub use pallet_balances::Call as BalancesCall;
-pub use sp_runtime::{Permill, Perbill};
+pub use sp_runtime::{Permill, Perbill, Percent};
 pub use frame_support::{
-	construct_runtime, parameter_types, StorageValue,
+	construct_runtime, parameter_types, StorageValue, PalletId,
 	traits::{KeyOwnerProofSystem, Randomness},
 	weights::{
 		Weight, IdentityFee,
@@ -59,6 +59,9 @@ pub type Balance = u128;
 /// Index of a transaction in the chain.
 pub type Index = u32;
 
+/// Index of a kitty.
+pub type KittyIndex = u32;
+
 /// A hash of some data used by the chain.
 pub type Hash = sp_core::H256;
 
@@ -98,10 +101,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 102,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 };
 
 /// This determines the average expected block time that we are targeting.
@@ -270,11 +273,12 @@ impl pallet_nicks::Config for Runtime {
 parameter_types! {
 	pub const ExistentialDeposit: u128 = 500;
 	pub const MaxLocks: u32 = 50;
+	pub const MaxReserves: u32 = 50;
 }
 
 impl pallet_balances::Config for Runtime {
 	type MaxLocks = MaxLocks;
-	type MaxReserves = ();
+	type MaxReserves = MaxReserves;
 	type ReserveIdentifier = [u8; 8];
 	/// The type for recording an account's balance.
 	type Balance = Balance;
@@ -297,6 +301,30 @@ impl pallet_transaction_payment::Config for Runtime {
 	type FeeMultiplierUpdate = ();
 }
 
+parameter_types! {
+	pub const AssetDeposit: u128 = 10_000;
+	pub const ApprovalDeposit: u128 = 500;
+	pub const StringLimit: u32 = 50;
+	pub const MetadataDepositBase: u128 = 1_000;
+	pub const MetadataDepositPerByte: u128 = 100;
+}
+
+impl pallet_assets::Config for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = u32;
+	type Currency = Balances;
+	type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+	type AssetDeposit = AssetDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type ApprovalDeposit = ApprovalDeposit;
+	type StringLimit = StringLimit;
+	type Freezer = ();
+	type Extra = ();
+	type WeightInfo = pallet_assets::weights::SubstrateWeight<Runtime>;
+}
+
 impl pallet_sudo::Config for Runtime {
 	type Event = Event;
 	type Call = Call;
@@ -309,15 +337,74 @@ impl pallet_template::Config for Runtime {
 
 parameter_types! {
 	pub const StakeForEachKitty: u128 = 1_000;
+	pub const KittiesPalletId: PalletId = PalletId(*b"py/kitty");
+	pub const ReferralReward: u128 = 100;
+	pub const MaxMintsPerBlock: u32 = 100;
+	pub const CertificateStake: u128 = 100;
+	pub const MaxCollectionNameLen: u32 = 32;
+	pub const ReversalWindow: BlockNumber = HOURS;
+	// Kitties don't expire on this chain.
+	pub const ExpiryBlocks: BlockNumber = 0;
+	pub const RenewalFee: u128 = 100;
+	pub const MaxKittiesOwned: u32 = 1_000;
+	pub const NewOwnerRebate: u128 = 500;
+	pub const FlipWindow: BlockNumber = HOURS;
+	pub const FlipThreshold: u32 = 5;
+	pub const FlipPenaltyFactor: u32 = 2;
+	pub const MaxQueuedBreeds: u32 = 100;
+	pub const MaxAuctionsEnding: u32 = 50;
+	pub const PostBuyLock: BlockNumber = 10 * MINUTES;
+	pub const RoyaltyPercent: Percent = Percent::from_percent(5);
+	pub const BreedingCooldown: BlockNumber = HOURS;
+	pub const MaxBatchSize: u32 = 50;
+	pub const MaxKittyNameLen: u32 = 32;
+	pub const MaxKittyUriLen: u32 = 128;
+	pub const NameDepositPerByte: u128 = 1;
+	pub const MaxLeasesEnding: u32 = 50;
+	pub const MaxSalesRecorded: u32 = 10;
+	pub const RevealDelay: BlockNumber = 3;
+	pub const MaxRevealsPerBlock: u32 = 50;
+	pub const KittiesSaleAsset: u32 = 0;
 }
 
 /// Configure the pallet-template in pallets/template.
 impl pallet_kitties::Config for Runtime {
 	type Event = Event;
 	type Randomness = RandomnessCollectiveFlip;
-	type KittyIndex = u32;
+	type KittyIndex = KittyIndex;
 	type StakeForEachKitty = StakeForEachKitty;
 	type Currency = Balances;
+	type PalletId = KittiesPalletId;
+	type ReferralReward = ReferralReward;
+	type MaxMintsPerBlock = MaxMintsPerBlock;
+	type CertificateStake = CertificateStake;
+	type MaxCollectionNameLen = MaxCollectionNameLen;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type ReversalWindow = ReversalWindow;
+	type ExpiryBlocks = ExpiryBlocks;
+	type RenewalFee = RenewalFee;
+	type MaxKittiesOwned = MaxKittiesOwned;
+	type NewOwnerRebate = NewOwnerRebate;
+	type FlipWindow = FlipWindow;
+	type FlipThreshold = FlipThreshold;
+	type FlipPenaltyFactor = FlipPenaltyFactor;
+	type MaxQueuedBreeds = MaxQueuedBreeds;
+	type MaxAuctionsEnding = MaxAuctionsEnding;
+	type PostBuyLock = PostBuyLock;
+	type RoyaltyPercent = RoyaltyPercent;
+	type BreedingCooldown = BreedingCooldown;
+	type MaxBatchSize = MaxBatchSize;
+	type MaxKittyNameLen = MaxKittyNameLen;
+	type MaxKittyUriLen = MaxKittyUriLen;
+	type NameDepositPerByte = NameDepositPerByte;
+	type MaxLeasesEnding = MaxLeasesEnding;
+	type MaxSalesRecorded = MaxSalesRecorded;
+	type RevealDelay = RevealDelay;
+	type MaxRevealsPerBlock = MaxRevealsPerBlock;
+	type GeneticsEngine = pallet_kitties::genetics::BitmaskMixer;
+	type Assets = Assets;
+	type SaleAsset = KittiesSaleAsset;
+	type WeightInfo = pallet_kitties::weights::SubstrateWeight<Runtime>;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -336,9 +423,10 @@ construct_runtime!(
 		TransactionPayment: pallet_transaction_payment::{Pallet, Storage},
 		Sudo: pallet_sudo::{Pallet, Call, Config<T>, Storage, Event<T>},
 		Nicks: pallet_nicks::{Pallet, Call, Storage, Event<T>},
+		Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
 		// Include the custom logic from the pallet-template in the runtime.
 		TemplateModule: pallet_template::{Pallet, Call, Storage, Event<T>},
-		KittiesModule: pallet_kitties::{Pallet, Call, Storage, Event<T>},
+		KittiesModule: pallet_kitties::{Pallet, Call, Storage, Event<T>, Config<T>},
 	}
 );
 
@@ -495,6 +583,63 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_kitties_rpc_runtime_api::KittiesApi<Block, AccountId, KittyIndex, Balance, BlockNumber> for Runtime {
+		fn get_kitty(kitty_id: KittyIndex) -> Option<pallet_kitties::Kitty> {
+			KittiesModule::kitties(kitty_id)
+		}
+		fn kitties_count() -> KittyIndex {
+			KittiesModule::kitties_count().unwrap_or_default()
+		}
+		fn listings(offset: u32, limit: u32) -> Vec<(KittyIndex, Balance)> {
+			KittiesModule::listings(offset, limit)
+		}
+		fn kitties_of(account: AccountId) -> Vec<(KittyIndex, pallet_kitties::Kitty)> {
+			KittiesModule::kitties_of(&account)
+		}
+		fn is_owner(account: AccountId, kitty_id: KittyIndex) -> bool {
+			KittiesModule::is_owner(&account, kitty_id)
+		}
+		fn dnas_of(ids: Vec<KittyIndex>) -> Vec<Option<[u8; 16]>> {
+			KittiesModule::dnas_of(ids)
+		}
+		fn dna_exists(dna: [u8; 16]) -> bool {
+			KittiesModule::dna_exists(dna)
+		}
+		fn distinct_owners() -> u32 {
+			KittiesModule::distinct_owners()
+		}
+		fn current_stake_for(account: AccountId) -> Balance {
+			KittiesModule::current_stake_for(&account)
+		}
+		fn kitty_flags(kitty_id: KittyIndex) -> pallet_kitties::KittyFlags {
+			KittiesModule::kitty_flags(kitty_id)
+		}
+		fn buyable_by(account: AccountId, limit: u32) -> Vec<KittyIndex> {
+			KittiesModule::buyable_by(&account, limit)
+		}
+		fn kitty_random_source(kitty_id: KittyIndex) -> Option<(BlockNumber, [u8; 8])> {
+			KittiesModule::kitty_random_source(kitty_id)
+		}
+		fn kitties_with_trait(selector: (u8, u8), limit: u32) -> Vec<KittyIndex> {
+			KittiesModule::kitties_with_trait(selector, limit)
+		}
+		fn shared_lineage(account_a: AccountId, account_b: AccountId, depth: u32) -> Vec<KittyIndex> {
+			KittiesModule::shared_lineage(&account_a, &account_b, depth)
+		}
+		fn kitty_metadata(kitty_id: KittyIndex) -> Option<pallet_kitties::Provenance<KittyIndex, BlockNumber>> {
+			KittiesModule::kitty_metadata(kitty_id)
+		}
+		fn last_sales(kitty_id: KittyIndex) -> Vec<(Balance, BlockNumber)> {
+			KittiesModule::last_sales(kitty_id).into_inner()
+		}
+		fn total_volume() -> Balance {
+			KittiesModule::total_volume()
+		}
+		fn floor_price() -> Option<Balance> {
+			KittiesModule::floor_price()
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn dispatch_benchmark(
@@ -525,6 +670,7 @@ impl_runtime_apis! {
 			add_benchmark!(params, batches, pallet_balances, Balances);
 			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
 			add_benchmark!(params, batches, pallet_template, TemplateModule);
+			add_benchmark!(params, batches, pallet_kitties, KittiesModule);
 
 			if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
 			Ok(batches)
@@ -0,0 +1,48 @@
+use frame_support::traits::GenesisBuild;
+use node_template_runtime::{AccountId, Balance, Block, BlockNumber, KittyIndex, Runtime};
+use pallet_kitties_rpc_runtime_api::runtime_decl_for_KittiesApi::KittiesApi;
+
+fn new_test_ext(kitties: Vec<(AccountId, [u8; 16])>) -> sp_io::TestExternalities {
+	let mut storage = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+
+	pallet_balances::GenesisConfig::<Runtime> {
+		balances: vec![(alice(), 1_000_000), (bob(), 1_000_000)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+
+	pallet_kitties::GenesisConfig::<Runtime> { kitties }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+
+	sp_io::TestExternalities::new(storage)
+}
+
+fn alice() -> AccountId {
+	AccountId::from([1u8; 32])
+}
+
+fn bob() -> AccountId {
+	AccountId::from([2u8; 32])
+}
+
+#[test]
+fn kitties_of_returns_the_owned_kitties() {
+	new_test_ext(vec![(alice(), [1u8; 16]), (bob(), [2u8; 16]), (alice(), [3u8; 16])]).execute_with(|| {
+		let kitties = <Runtime as KittiesApi<Block, AccountId, KittyIndex, Balance, BlockNumber>>::kitties_of(alice());
+		let kitties: Vec<(KittyIndex, [u8; 16])> = kitties.into_iter().map(|(id, kitty)| (id, kitty.dna)).collect();
+		assert_eq!(kitties, vec![(0, [1u8; 16]), (2, [3u8; 16])]);
+		assert!(<Runtime as KittiesApi<Block, AccountId, KittyIndex, Balance, BlockNumber>>::is_owner(bob(), 1));
+		assert_eq!(<Runtime as KittiesApi<Block, AccountId, KittyIndex, Balance, BlockNumber>>::distinct_owners(), 2);
+	});
+}
+
+#[test]
+fn get_kitty_and_kitties_count_work() {
+	new_test_ext(vec![(alice(), [1u8; 16]), (bob(), [2u8; 16])]).execute_with(|| {
+		assert_eq!(<Runtime as KittiesApi<Block, AccountId, KittyIndex, Balance, BlockNumber>>::kitties_count(), 2);
+		let kitty = <Runtime as KittiesApi<Block, AccountId, KittyIndex, Balance, BlockNumber>>::get_kitty(1);
+		assert_eq!(kitty.map(|kitty| kitty.dna), Some([2u8; 16]));
+		assert!(<Runtime as KittiesApi<Block, AccountId, KittyIndex, Balance, BlockNumber>>::get_kitty(2).is_none());
+	});
+}